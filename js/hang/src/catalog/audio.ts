@@ -38,6 +38,10 @@ export const AudioConfigSchema = z.object({
 	// NOTE: The audio "frame" duration depends on the codec, sample rate, etc.
 	// ex: AAC often uses 1024 samples per frame, so at 44100Hz, this would be 1024/44100 = 23ms
 	jitter: u53Schema.optional(),
+
+	// The gain in decibels that the player should apply to the decoded output.
+	// If not provided, the player should assume a gain of 0 dB.
+	gainDb: z.number().optional(),
 });
 
 export const AudioSchema = z
@@ -30,10 +30,17 @@ pub struct Audio {
 impl Audio {
 	/// Create a new audio track with the given extension and configuration.
 	pub fn create_track(&mut self, extension: &str, config: AudioConfig) -> moq_lite::Track {
+		self.create_named_track("audio", extension, config)
+	}
+
+	/// Create a new audio track using a custom base name instead of "audio", ex. "commentary0.aac".
+	///
+	/// This distinguishes multiple audio tracks in the same broadcast, like music and a microphone.
+	pub fn create_named_track(&mut self, base: &str, extension: &str, config: AudioConfig) -> moq_lite::Track {
 		for i in 0.. {
 			let name = match extension {
-				"" => format!("audio{}", i),
-				extension => format!("audio{}.{}", i, extension),
+				"" => format!("{}{}", base, i),
+				extension => format!("{}{}.{}", base, i, extension),
 			};
 
 			if let btree_map::Entry::Vacant(entry) = self.renditions.entry(name.clone()) {
@@ -98,4 +105,11 @@ pub struct AudioConfig {
 	/// ex: AAC often uses 1024 samples per frame, so at 44100Hz, this would be 1024/44100 = 23ms
 	#[serde(default)]
 	pub jitter: Option<moq_lite::Time>,
+
+	/// The gain in decibels that the player should apply to the decoded output.
+	///
+	/// This is the Opus output gain, used for loudness normalization.
+	/// If not provided, the player should assume a gain of 0 dB.
+	#[serde(default)]
+	pub gain_db: Option<f32>,
 }
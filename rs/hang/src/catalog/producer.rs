@@ -1,5 +1,5 @@
 use std::ops::{Deref, DerefMut};
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError};
 
 use crate::{Catalog, CatalogConsumer};
 
@@ -7,29 +7,79 @@ use crate::{Catalog, CatalogConsumer};
 ///
 /// The JSON catalog is updated when tracks are added/removed but is *not* automatically published.
 /// You'll have to call [`lock`](Self::lock) to update and publish the catalog.
+///
+/// The catalog is shared by every clone, so keep the lock scope minimal:
+/// - Batch related changes into a single guard, which also publishes a single update.
+/// - Don't hold the guard while doing unrelated work, like parsing media or creating tracks.
+/// - Use [`queue`](Self::queue) in `Drop` implementations, so teardown can never block.
 #[derive(Clone)]
 pub struct CatalogProducer {
 	/// Access to the underlying track producer.
 	pub track: moq_lite::TrackProducer,
 	current: Arc<Mutex<Catalog>>,
+
+	// Changes made while the catalog was locked elsewhere, applied by the next guard.
+	pending: Arc<Mutex<Vec<PendingUpdate>>>,
 }
 
+type PendingUpdate = Box<dyn FnOnce(&mut Catalog) + Send>;
+
 impl CatalogProducer {
 	/// Create a new catalog producer with the given track and initial catalog.
 	pub fn new(track: moq_lite::TrackProducer, init: Catalog) -> Self {
 		Self {
 			current: Arc::new(Mutex::new(init)),
+			pending: Default::default(),
 			track,
 		}
 	}
 
 	/// Get mutable access to the catalog, publishing it after any changes.
+	///
+	/// This blocks until any other guard is dropped.
+	/// A panic while holding the lock does not poison it; the catalog is still valid, if potentially stale.
 	pub fn lock(&mut self) -> CatalogGuard<'_> {
-		CatalogGuard {
-			catalog: self.current.lock().unwrap(),
+		let mut guard = CatalogGuard {
+			catalog: Some(self.current.lock().unwrap_or_else(PoisonError::into_inner)),
 			track: &mut self.track,
+			pending: &self.pending,
 			updated: false,
-		}
+		};
+		guard.apply_pending();
+		guard
+	}
+
+	/// Like [`lock`](Self::lock), but returns [None] instead of blocking if the catalog is already locked.
+	pub fn try_lock(&mut self) -> Option<CatalogGuard<'_>> {
+		let catalog = match self.current.try_lock() {
+			Ok(catalog) => catalog,
+			Err(TryLockError::Poisoned(err)) => err.into_inner(),
+			Err(TryLockError::WouldBlock) => return None,
+		};
+
+		let mut guard = CatalogGuard {
+			catalog: Some(catalog),
+			track: &mut self.track,
+			pending: &self.pending,
+			updated: false,
+		};
+		guard.apply_pending();
+		Some(guard)
+	}
+
+	/// Apply a change without blocking, queuing it if the catalog is already locked.
+	///
+	/// A queued change is applied and published by the current guard when it's dropped, or by the next [`lock`](Self::lock).
+	/// The change must not lock or queue on this catalog itself, which would deadlock.
+	pub fn queue(&mut self, update: impl FnOnce(&mut Catalog) + Send + 'static) {
+		self.pending
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.push(Box::new(update));
+
+		// If the catalog is unlocked, apply the queue ourselves and publish it when the guard is dropped.
+		// Otherwise the current guard hasn't unlocked yet, so it will apply the queue when dropped.
+		drop(self.try_lock());
 	}
 
 	/// Create a consumer for this catalog, receiving updates as they're published.
@@ -59,38 +109,147 @@ impl Default for CatalogProducer {
 ///
 /// Obtained via [`CatalogProducer::lock`].
 pub struct CatalogGuard<'a> {
-	catalog: MutexGuard<'a, Catalog>,
+	// Only [None] while being dropped.
+	catalog: Option<MutexGuard<'a, Catalog>>,
 	track: &'a mut moq_lite::TrackProducer,
+	pending: &'a Mutex<Vec<PendingUpdate>>,
 	updated: bool,
 }
 
+impl CatalogGuard<'_> {
+	// Apply any changes that were queued while the catalog was locked.
+	fn apply_pending(&mut self) {
+		let pending = std::mem::take(&mut *self.pending.lock().unwrap_or_else(PoisonError::into_inner));
+		for update in pending {
+			update(self);
+		}
+	}
+}
+
 impl<'a> Deref for CatalogGuard<'a> {
 	type Target = Catalog;
 
 	fn deref(&self) -> &Self::Target {
-		&self.catalog
+		self.catalog.as_deref().expect("catalog is unlocked")
 	}
 }
 
 impl<'a> DerefMut for CatalogGuard<'a> {
 	fn deref_mut(&mut self) -> &mut Self::Target {
 		self.updated = true;
-		&mut self.catalog
+		self.catalog.as_deref_mut().expect("catalog is unlocked")
 	}
 }
 
 impl Drop for CatalogGuard<'_> {
 	fn drop(&mut self) {
+		// Hold the queue until the catalog is unlocked, otherwise a change could be queued after we apply the queue.
+		// It would then wait for the next lock, which may never happen during teardown.
+		let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+		let mut catalog = self.catalog.take().expect("catalog is unlocked");
+
+		// Include any changes queued while we held the lock.
+		for update in pending.drain(..) {
+			update(&mut catalog);
+			self.updated = true;
+		}
+
 		// Avoid publishing if we didn't use `&mut self` at all.
-		if !self.updated {
-			return;
+		if self.updated {
+			let mut group = self.track.append_group();
+
+			// TODO decide if this should return an error, or be impossible to fail
+			let frame = catalog.to_string().expect("invalid catalog");
+			group.write_frame(frame);
+			group.close();
 		}
 
-		let mut group = self.track.append_group();
+		// Unlock the catalog before the queue.
+		drop(catalog);
+		drop(pending);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn try_lock() {
+		let mut producer = CatalogProducer::default();
+		let mut other = producer.clone();
+
+		let guard = producer.lock();
+		assert!(other.try_lock().is_none());
+		drop(guard);
+
+		assert!(other.try_lock().is_some());
+	}
+
+	#[test]
+	fn queue() {
+		let mut producer = CatalogProducer::default();
+		let mut other = producer.clone();
+
+		// Applied immediately when unlocked.
+		other.queue(|catalog| catalog.preview = Some(moq_lite::Track::new("a")));
+		assert_eq!(producer.lock().preview, Some(moq_lite::Track::new("a")));
+
+		// Otherwise applied when the guard is dropped.
+		let guard = producer.lock();
+		other.queue(|catalog| catalog.preview = Some(moq_lite::Track::new("b")));
+		assert_eq!(guard.preview, Some(moq_lite::Track::new("a")));
+		drop(guard);
+
+		assert_eq!(producer.lock().preview, Some(moq_lite::Track::new("b")));
+	}
+
+	#[test]
+	fn queue_while_dropping() {
+		let mut producer = CatalogProducer::default();
+		let mut other = producer.clone();
+		let (tx, rx) = std::sync::mpsc::channel();
+
+		// This change is applied while the guard is being dropped, giving the other thread a chance to queue.
+		let guard = producer.lock();
+		other.queue(move |catalog| {
+			catalog.preview = Some(moq_lite::Track::new("a"));
+			tx.send(()).unwrap();
+			std::thread::sleep(std::time::Duration::from_millis(50));
+		});
+
+		let handle = std::thread::spawn(move || {
+			rx.recv().unwrap();
+			other.queue(|catalog| catalog.preview = Some(moq_lite::Track::new("b")));
+		});
+
+		drop(guard);
+		handle.join().unwrap();
+
+		// Check without locking, because the next lock would apply the queue anyway.
+		assert!(
+			producer.pending.lock().unwrap().is_empty(),
+			"queued change was not applied"
+		);
+		assert_eq!(
+			producer.current.lock().unwrap().preview,
+			Some(moq_lite::Track::new("b"))
+		);
+	}
+
+	#[test]
+	fn lock_after_panic() {
+		let mut producer = CatalogProducer::default();
+
+		let mut other = producer.clone();
+		std::thread::spawn(move || {
+			let _guard = other.lock();
+			panic!("poison the lock");
+		})
+		.join()
+		.unwrap_err();
 
-		// TODO decide if this should return an error, or be impossible to fail
-		let frame = self.catalog.to_string().expect("invalid catalog");
-		group.write_frame(frame);
-		group.close();
+		assert!(producer.try_lock().is_some());
+		producer.lock().video.renditions.clear();
 	}
 }
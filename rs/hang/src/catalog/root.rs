@@ -164,6 +164,7 @@ mod test {
 				description: None,
 				container: Container::Legacy,
 				jitter: None,
+				gain_db: None,
 			},
 		);
 
@@ -16,6 +16,9 @@ use crate::Error;
 ///
 /// The consumer can skip groups that are too far behind to maintain low latency.
 /// Configure the maximum acceptable delay through the consumer's latency settings.
+///
+/// To read every frame instead, like when recording or testing, use a maximum latency longer than the track.
+/// Newer groups are then buffered and read in sequence order, but a group older than the one being read is still skipped.
 pub struct OrderedConsumer {
 	pub track: moq_lite::TrackConsumer,
 
@@ -227,3 +230,41 @@ impl std::ops::Deref for GroupReader {
 		&self.group
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::container::OrderedProducer;
+	use bytes::{Buf, Bytes};
+
+	#[tokio::test]
+	async fn round_trip() {
+		let track = moq_lite::Track::new("test").produce();
+		let mut consumer = OrderedConsumer::new(track.consume(), std::time::Duration::from_secs(3600));
+		let mut producer = OrderedProducer::new(track);
+
+		// Two groups, each starting with a keyframe.
+		let frames = [(0, true, "a"), (20, false, "b"), (40, true, "c"), (60, false, "d")];
+
+		for (ms, keyframe, payload) in frames {
+			let frame = Frame {
+				timestamp: Timestamp::from_micros(ms * 1_000).unwrap(),
+				keyframe,
+				payload: Bytes::from(payload).into(),
+			};
+			producer.write(frame).unwrap();
+		}
+
+		producer.flush().unwrap();
+		producer.track.close();
+
+		for (ms, keyframe, payload) in frames {
+			let mut frame = consumer.read().await.unwrap().unwrap();
+			assert_eq!(frame.timestamp, Timestamp::from_micros(ms * 1_000).unwrap());
+			assert_eq!(frame.keyframe, keyframe);
+			assert_eq!(frame.payload.copy_to_bytes(frame.payload.remaining()), payload);
+		}
+
+		assert!(consumer.read().await.unwrap().is_none());
+	}
+}
@@ -79,6 +79,11 @@ impl OrderedProducer {
 		Ok(())
 	}
 
+	/// The sequence number of the current group, or [None] if there's no group in progress.
+	pub fn group_sequence(&self) -> Option<u64> {
+		self.group.as_ref().map(|group| group.info.sequence)
+	}
+
 	/// Create a consumer for this track.
 	///
 	/// Multiple consumers can be created from the same producer, each receiving
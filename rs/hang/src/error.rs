@@ -72,6 +72,10 @@ pub enum Error {
 
 	#[error("unknown format: {0}")]
 	UnknownFormat(String),
+
+	/// The format is known, but support was not compiled in via its feature flag.
+	#[error("format not enabled: {0}")]
+	FormatNotEnabled(String),
 }
 
 /// A Result type alias for hang operations.
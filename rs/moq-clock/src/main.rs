@@ -12,6 +12,7 @@ mod clock;
 use moq_lite::*;
 
 #[derive(Parser, Clone)]
+#[command(mut_arg("client-token", |arg| arg.visible_alias("token")))]
 pub struct Config {
 	/// Connect to the given URL starting with https://
 	#[arg(long)]
@@ -1,102 +1,103 @@
-use anyhow::Context;
+use super::asc::AudioSpecificConfig;
+use super::loas::{self, Loas};
+use super::output::{Output, TrackConfig};
+use super::{
+	DecoderBuf, ImportOptions, Importer, LengthPrefixed, MediaDecoder, MediaInfo, SeekIndex, ensure_frame_size,
+};
+
 use buf_list::BufList;
 use bytes::Buf;
 
 /// AAC decoder, initialized via AudioSpecificConfig (variable length from ESDS box).
+///
+/// Raw AAC frames are decoded with [Self::decode], while LOAS/LATM streams are decoded with [Self::decode_stream].
+/// Length prefixed raw frames can also be decoded with [Self::decode_stream] via [Self::set_length_prefixed].
 pub struct Aac {
-	broadcast: moq_lite::BroadcastProducer,
-	catalog: hang::catalog::CatalogProducer,
-	track: Option<hang::container::OrderedProducer>,
+	// The track being produced, named using the base name, and the options.
+	output: Output,
+
 	zero: Option<tokio::time::Instant>,
+
+	// Caches the StreamMuxConfig when decoding LOAS.
+	loas: Loas,
+
+	// The length prefix used to split a stream into raw frames, instead of LOAS.
+	length_prefixed: Option<LengthPrefixed>,
 }
 
 impl Aac {
 	pub fn new(broadcast: moq_lite::BroadcastProducer, catalog: hang::catalog::CatalogProducer) -> Self {
+		Self::new_named(broadcast, catalog, "audio")
+	}
+
+	/// Create a decoder that names its track using the given base name instead of "audio", ex. "commentary0.aac".
+	///
+	/// This distinguishes multiple audio importers on the same broadcast.
+	pub fn new_named(
+		broadcast: moq_lite::BroadcastProducer,
+		catalog: hang::catalog::CatalogProducer,
+		name: impl Into<String>,
+	) -> Self {
 		Self {
-			broadcast,
-			catalog,
-			track: None,
+			output: Output::named(broadcast, catalog, name),
 			zero: None,
+			loas: Loas::default(),
+			length_prefixed: None,
 		}
 	}
 
-	pub fn initialize<T: Buf>(&mut self, buf: &mut T) -> anyhow::Result<()> {
-		anyhow::ensure!(buf.remaining() >= 2, "AudioSpecificConfig must be at least 2 bytes");
+	/// Split the stream passed to [Self::decode_stream] using a length prefix before each raw frame, instead of LOAS.
+	///
+	/// Unlike LOAS, the stream does not contain the AudioSpecificConfig, so [Self::initialize] is required.
+	pub fn set_length_prefixed(&mut self, length_prefixed: Option<LengthPrefixed>) {
+		self.length_prefixed = length_prefixed;
+	}
 
-		// Parse AudioSpecificConfig (ISO 14496-3)
-		// This parser handles variable-length configurations including:
-		// - Basic formats (object_type < 31)
-		// - Extended formats (object_type == 31)
-		// - SBR/PS extensions
-		// - Explicit sample rates (freq_index == 15)
-
-		// Read first byte
-		let b0 = buf.get_u8();
-		let mut object_type = b0 >> 3;
-		let mut freq_index;
-
-		// Handle extended audioObjectType (object_type == 31)
-		let (profile, sample_rate, channel_count) = if object_type == 31 {
-			anyhow::ensure!(
-				buf.remaining() >= 2,
-				"extended audioObjectType requires 2 additional bytes"
-			);
-			// Extended format: next 6 bits are the extended object_type (32-63)
-			// Bits 5-7 of b0 are the first 3 bits of extended object_type
-			let b_ext = buf.get_u8();
-			// Bits 0-2 of b_ext are the last 3 bits of extended object_type
-			let audio_object_type_ext = ((b0 & 0x07) << 3) | ((b_ext >> 5) & 0x07);
-			object_type = 32 + audio_object_type_ext;
-			// Bits 3-6 of b_ext are samplingFrequencyIndex (4 bits)
-			freq_index = (b_ext >> 1) & 0x0F;
-			// Bit 0 of b_ext is the first bit of channelConfiguration
-			let channel_config_high = b_ext & 0x01;
-
-			// Read next byte for rest of channelConfiguration
-			anyhow::ensure!(buf.remaining() >= 1, "AudioSpecificConfig incomplete");
-			let b1 = buf.get_u8();
-			// Bits 5-7 of b1 are the remaining 3 bits of channelConfiguration
-			let channel_config = (channel_config_high << 3) | ((b1 >> 5) & 0x07);
-
-			let sample_rate = sample_rate_from_index(freq_index, buf)?;
-			let channel_count = channel_count_from_config(channel_config);
-
-			// Consume any remaining extension data
-			if buf.remaining() > 0 {
-				buf.advance(buf.remaining());
-			}
+	/// The options used by this importer.
+	pub fn options(&self) -> &ImportOptions {
+		self.output.options()
+	}
 
-			(object_type, sample_rate, channel_count)
-		} else {
-			// Standard format: bits 5-7 of b0 are first 3 bits of freq_index
-			freq_index = (b0 & 0x07) << 1;
+	/// Replace every option at once, see [ImportOptions].
+	///
+	/// This only applies to tracks created after this call, so call it before decoding.
+	pub fn set_options(&mut self, options: ImportOptions) {
+		self.output.set_options(options);
+	}
 
-			// Read second byte
-			anyhow::ensure!(buf.remaining() >= 1, "AudioSpecificConfig incomplete");
-			let b1 = buf.get_u8();
+	/// The seek index for the current track, or [None] if not enabled.
+	///
+	/// Every packet that starts a group is recorded, and the index is reset when a new track is created.
+	pub fn seek_index(&self) -> Option<&SeekIndex> {
+		self.output.seek_index()
+	}
 
-			// Complete frequency index (bit 7 of b1 is bit 0 of freq_index)
-			freq_index |= (b1 >> 7) & 0x01;
+	/// The media seen so far in dry run mode, or [None] if not enabled.
+	pub fn media_info(&self) -> Option<&MediaInfo> {
+		self.output.media_info()
+	}
 
-			// Channel configuration
-			let channel_config = (b1 >> 3) & 0x0F;
+	/// Advertise a fixed jitter in the catalog, used by the player to size its jitter buffer.
+	///
+	/// This only applies to tracks created after this call.
+	pub fn set_jitter(&mut self, jitter: Option<std::time::Duration>) {
+		self.output.set_jitter(jitter);
+	}
 
-			let sample_rate = sample_rate_from_index(freq_index, buf)?;
-			let channel_count = channel_count_from_config(channel_config);
+	pub fn initialize<T: Buf>(&mut self, buf: &mut T) -> anyhow::Result<()> {
+		anyhow::ensure!(buf.remaining() >= 2, "AudioSpecificConfig must be at least 2 bytes");
 
-			// Consume any remaining extension data (SBR, PS, etc.)
-			// AudioSpecificConfig can have variable-length extensions that we don't need to parse.
-			// Since we've already extracted the essential info (object_type, sample_rate, channels),
-			// we'll consume any remaining bytes to ensure the buffer is properly advanced.
-			// This makes the parser robust to different AAC variants from OBS and other sources.
-			if buf.remaining() > 0 {
-				buf.advance(buf.remaining());
-			}
+		// Parse AudioSpecificConfig (ISO 14496-3), consuming the entire buffer.
+		let asc = AudioSpecificConfig::parse(&buf.copy_to_bytes(buf.remaining()))?;
 
-			(object_type, sample_rate, channel_count)
-		};
+		let profile = asc.object_type;
+		let sample_rate = asc.output_sample_rate();
+		let mut channel_count = channel_count_from_config(asc.channel_config);
 
-		let mut catalog = self.catalog.lock();
+		// Parametric stereo upmixes a mono core to stereo.
+		if asc.ps && channel_count == 1 {
+			channel_count = 2;
+		}
 
 		let config = hang::catalog::AudioConfig {
 			codec: hang::catalog::AAC { profile }.into(),
@@ -105,40 +106,109 @@ impl Aac {
 			bitrate: None,
 			description: None,
 			container: hang::catalog::Container::Legacy,
-			jitter: None,
+			jitter: self.output.jitter()?,
+			gain_db: None,
 		};
-		let track = catalog.audio.create_track("aac", config.clone());
-		tracing::debug!(name = ?track.name, ?config, "starting track");
 
-		self.track = Some(self.broadcast.create_track(track).into());
+		self.output.replace_track("aac", TrackConfig::Audio(config));
 
 		Ok(())
 	}
 
 	pub fn decode<T: Buf>(&mut self, buf: &mut T, pts: Option<hang::container::Timestamp>) -> anyhow::Result<()> {
-		let pts = self.pts(pts)?;
-		let track = self.track.as_mut().context("not initialized")?;
-
 		// Create a BufList at chunk boundaries, potentially avoiding allocations.
 		let mut payload = BufList::new();
 		while !buf.chunk().is_empty() {
 			payload.push_chunk(buf.copy_to_bytes(buf.chunk().len()));
 		}
 
+		self.decode_bytes(payload, pts)
+	}
+
+	/// Decode a frame that is already reference counted, without copying the payload.
+	///
+	/// Prefer this over [Self::decode] when the frame is a [bytes::Bytes] or [BufList].
+	pub fn decode_bytes<B: Into<BufList>>(
+		&mut self,
+		payload: B,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		let payload: BufList = payload.into();
+		ensure_frame_size(payload.num_bytes(), self.output.max_frame_size())?;
+
+		let pts = self.pts(pts)?;
+
 		let frame = hang::container::Frame {
 			timestamp: pts,
 			keyframe: true, // Audio frames are always keyframes
 			payload,
 		};
 
-		track.write(frame)?;
-		track.flush()?; // We know the next frame will be a keyframe, so flush the current group.
+		// Only counted in dry run mode.
+		if let Some(track) = self.output.write(frame)? {
+			track.flush()?; // We know the next frame will be a keyframe, so flush the current group.
+		}
+
+		Ok(())
+	}
+
+	/// Decode as much of a LOAS/LATM stream as possible from the given buffer.
+	///
+	/// The catalog is initialized from the in-band StreamMuxConfig, so [Self::initialize] is not required.
+	/// If the buffer ends with a partial LOAS frame, it is left unconsumed and more data is needed.
+	///
+	/// If [Self::set_length_prefixed] was called, the stream instead contains length prefixed raw frames.
+	pub fn decode_stream<T: Buf + AsRef<[u8]>>(
+		&mut self,
+		buf: &mut T,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		if let Some(prefix) = self.length_prefixed {
+			while let Some(frame) = prefix.next(buf, self.output.max_frame_size())? {
+				self.decode_bytes(frame, pts)?;
+			}
+
+			return Ok(());
+		}
+
+		// The syncword spans the first two bytes, so wait for both before checking it.
+		if buf.remaining() >= 2 && !loas::is_loas(buf.as_ref()) {
+			anyhow::bail!("AAC stream decoding requires LOAS framing");
+		}
+
+		while let Some(size) = loas::frame_size(buf.as_ref())? {
+			let element = self.loas.decode(&buf.as_ref()[..size])?;
+			buf.advance(size);
+
+			// A new config replaces the previous track.
+			if let Some(mut config) = element.config {
+				self.initialize(&mut config)?;
+			}
+
+			for payload in element.payloads {
+				self.decode_bytes(payload, pts)?;
+			}
+		}
 
 		Ok(())
 	}
 
 	pub fn is_initialized(&self) -> bool {
-		self.track.is_some()
+		self.output.is_initialized()
+	}
+
+	/// The name of the track in the broadcast, or [None] if not initialized yet.
+	///
+	/// A new track is created if a LOAS stream changes its config, so this may change after each decode.
+	pub fn track_name(&self) -> Option<&str> {
+		self.output.track_name()
+	}
+
+	/// The track being produced, or [None] if not initialized yet.
+	///
+	/// This can be used to subscribe to the track locally via [hang::container::OrderedProducer::consume].
+	pub fn track(&self) -> Option<&hang::container::OrderedProducer> {
+		self.output.track()
 	}
 
 	fn pts(&mut self, hint: Option<hang::container::Timestamp>) -> anyhow::Result<hang::container::Timestamp> {
@@ -153,30 +223,52 @@ impl Aac {
 	}
 }
 
-impl Drop for Aac {
-	fn drop(&mut self) {
-		if let Some(track) = self.track.take() {
-			tracing::debug!(name = ?track.info.name, "ending track");
-			self.catalog.lock().audio.remove_track(&track.info);
-		}
+impl MediaDecoder for Aac {
+	fn initialize(&mut self, mut buf: &mut dyn DecoderBuf) -> anyhow::Result<()> {
+		Aac::initialize(self, &mut buf)
+	}
+
+	fn decode_frame(
+		&mut self,
+		mut buf: &mut dyn DecoderBuf,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		Aac::decode(self, &mut buf, pts)
+	}
+
+	fn decode_stream(
+		&mut self,
+		mut buf: &mut dyn DecoderBuf,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		Aac::decode_stream(self, &mut buf, pts)
+	}
+
+	fn is_initialized(&self) -> bool {
+		Aac::is_initialized(self)
+	}
+
+	fn track_name(&self) -> Option<&str> {
+		Aac::track_name(self)
 	}
 }
 
-fn sample_rate_from_index<T: Buf>(freq_index: u8, buf: &mut T) -> anyhow::Result<u32> {
-	const SAMPLE_RATES: [u32; 13] = [
-		96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
-	];
+impl Importer for Aac {
+	fn options(&self) -> &ImportOptions {
+		Aac::options(self)
+	}
+
+	fn set_options(&mut self, options: ImportOptions) {
+		Aac::set_options(self, options)
+	}
 
-	if freq_index == 15 {
-		anyhow::ensure!(buf.remaining() >= 3, "explicit sample rate requires 3 additional bytes");
-		let rate_bytes = [buf.get_u8(), buf.get_u8(), buf.get_u8()];
-		return Ok(((rate_bytes[0] as u32) << 16) | ((rate_bytes[1] as u32) << 8) | (rate_bytes[2] as u32));
+	fn seek_index(&self) -> Option<&SeekIndex> {
+		Aac::seek_index(self)
 	}
 
-	SAMPLE_RATES
-		.get(freq_index as usize)
-		.copied()
-		.context("unsupported sample rate index")
+	fn media_info(&self) -> Option<&MediaInfo> {
+		Aac::media_info(self)
+	}
 }
 
 fn channel_count_from_config(channel_config: u8) -> u32 {
@@ -189,3 +281,249 @@ fn channel_count_from_config(channel_config: u8) -> u32 {
 		2
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use bytes::Bytes;
+
+	// AAC-LC, 44.1kHz, stereo
+	const ASC: [u8; 2] = [0x12, 0x10];
+
+	fn setup() -> (Aac, moq_lite::BroadcastProducer) {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut aac = Aac::new(broadcast.clone(), catalog);
+		aac.initialize(&mut &ASC[..]).unwrap();
+		(aac, broadcast)
+	}
+
+	#[tokio::test]
+	async fn decode_bytes_is_zero_copy() {
+		let (mut aac, _broadcast) = setup();
+		let mut consumer = aac.track().unwrap().consume(std::time::Duration::from_secs(1));
+
+		let payload = Bytes::from_static(&[0x21, 0x10, 0x05, 0x00]);
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+		aac.decode_bytes(payload.clone(), Some(pts)).unwrap();
+
+		let frame = consumer.read().await.unwrap().unwrap();
+		let chunk = frame.payload.get_chunk(0).unwrap();
+		assert_eq!(frame.payload.num_chunks(), 1);
+		assert_eq!(chunk.as_ptr(), payload.as_ptr());
+	}
+
+	#[tokio::test]
+	async fn decode_shares_bytes_allocation() {
+		let (mut aac, _broadcast) = setup();
+		let mut consumer = aac.track().unwrap().consume(std::time::Duration::from_secs(1));
+
+		let payload = Bytes::from(vec![0x21, 0x10, 0x05, 0x00]);
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+		aac.decode(&mut payload.clone(), Some(pts)).unwrap();
+
+		let frame = consumer.read().await.unwrap().unwrap();
+		assert_eq!(frame.payload.get_chunk(0).unwrap().as_ptr(), payload.as_ptr());
+	}
+
+	#[test]
+	fn initialize_he_aac_output_rate() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+		let mut aac = Aac::new(broadcast, catalog.clone());
+
+		// HE-AACv2 with a 24kHz mono core, which plays back at 48kHz stereo.
+		aac.initialize(&mut &[0xeb, 0x09, 0x88, 0x00][..]).unwrap();
+
+		let config = catalog.lock().audio.renditions[aac.track_name().unwrap()].clone();
+		assert_eq!(config.sample_rate, 48000);
+		assert_eq!(config.channel_count, 2);
+	}
+
+	// Build a LOAS frame, optionally including a StreamMuxConfig for AAC-LC 44.1kHz stereo.
+	fn loas_frame(config: bool, payload: &[u8]) -> Vec<u8> {
+		let mut bits = Vec::new();
+		let mut write = |size: usize, value: u32| {
+			for i in (0..size).rev() {
+				bits.push((value >> i) & 1 == 1);
+			}
+		};
+
+		write(1, !config as u32); // useSameStreamMux
+		if config {
+			write(1, 0); // audioMuxVersion
+			write(1, 1); // allStreamsSameTimeFraming
+			write(6, 0); // numSubFrames
+			write(4, 0); // numProgram
+			write(3, 0); // numLayer
+			write(16, u16::from_be_bytes(ASC) as u32); // AudioSpecificConfig
+			write(3, 0); // frameLengthType
+			write(8, 0xff); // latmBufferFullness
+			write(1, 0); // otherDataPresent
+			write(1, 0); // crcCheckPresent
+		}
+
+		write(8, payload.len() as u32); // PayloadLengthInfo
+		for byte in payload {
+			write(8, *byte as u32); // PayloadMux
+		}
+
+		let element: Vec<u8> = bits
+			.chunks(8)
+			.map(|chunk| {
+				chunk
+					.iter()
+					.enumerate()
+					.fold(0, |acc, (i, bit)| acc | ((*bit as u8) << (7 - i)))
+			})
+			.collect();
+
+		let header = (0x2b7 << 13) | element.len() as u32;
+		let mut frame = header.to_be_bytes()[1..].to_vec();
+		frame.extend(element);
+		frame
+	}
+
+	#[tokio::test]
+	async fn decode_stream_loas() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+		let mut aac = Aac::new(broadcast, catalog.clone());
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+
+		// The first frame contains the StreamMuxConfig, which initializes the track.
+		let mut buf = Bytes::from(loas_frame(true, &[0x01, 0x02, 0x03]));
+		aac.decode_stream(&mut buf, Some(pts)).unwrap();
+		assert!(aac.is_initialized());
+		assert!(buf.is_empty());
+
+		{
+			let catalog = catalog.lock();
+			let config = catalog.audio.renditions.values().next().unwrap();
+			assert_eq!(config.sample_rate, 44100);
+			assert_eq!(config.channel_count, 2);
+		}
+
+		let mut consumer = aac.track().unwrap().consume(std::time::Duration::from_secs(1));
+
+		// The following frames reuse the cached config, with a partial frame at the end.
+		let mut data = loas_frame(false, &[0x04, 0x05]);
+		data.extend(loas_frame(false, &[0x06, 0x07, 0x08]));
+		let partial = loas_frame(false, &[0x09, 0x0a, 0x0b, 0x0c]);
+		data.extend(&partial[..4]);
+
+		let mut buf = Bytes::from(data);
+		aac.decode_stream(&mut buf, Some(pts)).unwrap();
+		assert_eq!(buf.as_ref(), &partial[..4]);
+
+		for expected in [&[0x01, 0x02, 0x03][..], &[0x04, 0x05], &[0x06, 0x07, 0x08]] {
+			let mut frame = consumer.read().await.unwrap().unwrap();
+			assert_eq!(
+				frame.payload.copy_to_bytes(frame.payload.remaining()).as_ref(),
+				expected
+			);
+		}
+
+		// Still a single track in the catalog.
+		assert_eq!(catalog.lock().audio.renditions.len(), 1);
+	}
+
+	#[test]
+	fn decode_stream_loas_partial_syncword() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut aac = Aac::new(broadcast, catalog);
+
+		// A read that ends after the first byte of the syncword needs more data.
+		let mut data = loas_frame(true, &[0x01, 0x02, 0x03]);
+		data.push(0x56);
+
+		let mut buf = Bytes::from(data);
+		aac.decode_stream(&mut buf, None).unwrap();
+		assert_eq!(buf.as_ref(), &[0x56]);
+
+		// Anything else is still rejected.
+		let mut buf = Bytes::from_static(&[0xff, 0xf1]);
+		assert!(aac.decode_stream(&mut buf, None).is_err());
+	}
+
+	#[tokio::test]
+	async fn decode_stream_length_prefixed() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut aac = Aac::new(broadcast, catalog);
+		aac.set_length_prefixed(Some(LengthPrefixed::U32Le));
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+
+		// AAC-LC, 44.1kHz, stereo
+		aac.initialize(&mut &[0x12, 0x10][..]).unwrap();
+		let mut consumer = aac.track().unwrap().consume(std::time::Duration::from_secs(1));
+
+		// Two complete frames followed by a partial frame.
+		let mut data = vec![0x02, 0x00, 0x00, 0x00, 0x01, 0x02];
+		data.extend([0x03, 0x00, 0x00, 0x00, 0x03, 0x04, 0x05]);
+		data.extend([0x04, 0x00, 0x00]);
+
+		let mut buf = Bytes::from(data);
+		aac.decode_stream(&mut buf, Some(pts)).unwrap();
+		assert_eq!(buf.as_ref(), &[0x04, 0x00, 0x00]);
+
+		for expected in [&[0x01, 0x02][..], &[0x03, 0x04, 0x05]] {
+			let mut frame = consumer.read().await.unwrap().unwrap();
+			assert_eq!(
+				frame.payload.copy_to_bytes(frame.payload.remaining()).as_ref(),
+				expected
+			);
+		}
+	}
+
+	#[test]
+	fn named_tracks() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+
+		let mut music = Aac::new_named(broadcast.clone(), catalog.clone(), "music");
+		let mut mic = Aac::new_named(broadcast, catalog.clone(), "mic");
+
+		// AAC-LC, 44.1kHz, stereo
+		music.initialize(&mut &[0x12, 0x10][..]).unwrap();
+		mic.initialize(&mut &[0x12, 0x10][..]).unwrap();
+
+		assert_eq!(music.track_name(), Some("music0.aac"));
+		assert_eq!(mic.track_name(), Some("mic0.aac"));
+
+		let names: Vec<_> = catalog.lock().audio.renditions.keys().cloned().collect();
+		assert_eq!(names, ["mic0.aac", "music0.aac"]);
+
+		// Dropping removes the same resolved name.
+		drop(music);
+		let names: Vec<_> = catalog.lock().audio.renditions.keys().cloned().collect();
+		assert_eq!(names, ["mic0.aac"]);
+	}
+
+	#[test]
+	fn seek_index_records_frames() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+
+		let mut aac = Aac::new(broadcast, catalog);
+		aac.set_options(ImportOptions {
+			seek_index: Some(SeekIndex::new()),
+			..Default::default()
+		});
+		aac.initialize(&mut &ASC[..]).unwrap();
+
+		for ms in [0, 23] {
+			let pts = hang::container::Timestamp::from_micros(ms * 1_000).unwrap();
+			aac.decode(&mut &[0x21, 0x10, 0x05, 0x00][..], Some(pts)).unwrap();
+		}
+
+		// Every AAC frame starts a group.
+		let groups: Vec<_> = aac.seek_index().unwrap().points().map(|point| point.group).collect();
+		assert_eq!(groups, [0, 1]);
+
+		// A new config replaces the track, which starts its groups over.
+		aac.initialize(&mut &ASC[..]).unwrap();
+		assert!(aac.seek_index().unwrap().is_empty());
+	}
+}
@@ -1,16 +1,71 @@
-use anyhow::{self};
+use std::fmt;
+
+use anyhow::{self, Context};
 use bytes::{Buf, Bytes};
+use hang::container::Timestamp;
+
+use super::{MAX_FRAME_SIZE, ensure_frame_size};
 
 pub const START_CODE: Bytes = Bytes::from_static(&[0, 0, 0, 1]);
 
+/// The location of invalid Annex B data within a stream.
+///
+/// This is attached as context to parsing errors and can be retrieved via [anyhow::Error::downcast_ref].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnnexBError {
+	/// The absolute byte offset into the stream where the invalid data begins.
+	pub offset: u64,
+
+	/// The offending bytes, truncated to the size of a start code.
+	pub bytes: Bytes,
+}
+
+impl fmt::Display for AnnexBError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"invalid Annex B data at offset {}: {:02x?}",
+			self.offset,
+			self.bytes.as_ref()
+		)
+	}
+}
+
 pub struct NalIterator<'a, T: Buf + AsRef<[u8]> + 'a> {
 	buf: &'a mut T,
 	start: Option<usize>,
+	offset: u64,
+	max_size: usize,
 }
 
 impl<'a, T: Buf + AsRef<[u8]> + 'a> NalIterator<'a, T> {
 	pub fn new(buf: &'a mut T) -> Self {
-		Self { buf, start: None }
+		Self {
+			buf,
+			start: None,
+			offset: 0,
+			max_size: MAX_FRAME_SIZE,
+		}
+	}
+
+	/// Return an error for any NAL unit larger than the given size.
+	///
+	/// This includes a partial NAL unit without a start code, so an endless run can't be buffered forever.
+	pub fn with_max_size(mut self, max_size: usize) -> Self {
+		self.max_size = max_size;
+		self
+	}
+
+	/// The buffer starts at the given absolute offset into the stream.
+	/// The offset is only used to report the location of errors.
+	pub fn with_offset(mut self, offset: u64) -> Self {
+		self.offset = offset;
+		self
+	}
+
+	/// The absolute offset of the remaining buffer within the stream.
+	pub fn offset(&self) -> u64 {
+		self.offset
 	}
 
 	/// Assume the buffer ends with a NAL unit and flush it.
@@ -19,18 +74,27 @@ impl<'a, T: Buf + AsRef<[u8]> + 'a> NalIterator<'a, T> {
 		let start = match self.start {
 			Some(start) => start,
 			None => {
-				let Some(start) = after_start_code(self.buf.as_ref())? else {
+				let Some(start) = self.after_start_code()? else {
 					return Ok(None);
 				};
 				start
 			}
 		};
 
+		ensure_frame_size(self.buf.remaining() - start, self.max_size)?;
 		self.buf.advance(start);
 
 		let nal = self.buf.copy_to_bytes(self.buf.remaining());
 		Ok(Some(nal))
 	}
+
+	fn after_start_code(&self) -> anyhow::Result<Option<usize>> {
+		let buf = self.buf.as_ref();
+		after_start_code(buf).with_context(|| AnnexBError {
+			offset: self.offset,
+			bytes: Bytes::copy_from_slice(&buf[..buf.len().min(START_CODE.len())]),
+		})
+	}
 }
 
 impl<'a, T: Buf + AsRef<[u8]> + 'a> Iterator for NalIterator<'a, T> {
@@ -39,14 +103,26 @@ impl<'a, T: Buf + AsRef<[u8]> + 'a> Iterator for NalIterator<'a, T> {
 	fn next(&mut self) -> Option<Self::Item> {
 		let start = match self.start {
 			Some(start) => start,
-			None => match after_start_code(self.buf.as_ref()).transpose()? {
+			None => match self.after_start_code().transpose()? {
 				Ok(start) => start,
 				Err(err) => return Some(Err(err)),
 			},
 		};
 
-		let (size, new_start) = find_start_code(&self.buf.as_ref()[start..])?;
+		let Some((size, new_start)) = find_start_code(&self.buf.as_ref()[start..]) else {
+			// Wait for the next start code, unless the partial NAL unit is already too large.
+			return match ensure_frame_size(self.buf.remaining() - start, self.max_size) {
+				Ok(()) => None,
+				Err(err) => Some(Err(err)),
+			};
+		};
+
+		if let Err(err) = ensure_frame_size(size, self.max_size) {
+			return Some(Err(err));
+		}
+
 		self.buf.advance(start);
+		self.offset += (start + size) as u64;
 
 		let nal = self.buf.copy_to_bytes(size);
 		self.start = Some(new_start);
@@ -54,6 +130,72 @@ impl<'a, T: Buf + AsRef<[u8]> + 'a> Iterator for NalIterator<'a, T> {
 	}
 }
 
+/// Assigns a timestamp to each access unit split from a single buffer.
+///
+/// The first access unit uses the timestamp of the buffer, and each one after it is stepped by the frame duration.
+/// Without the timing info in the SPS, they all share the same timestamp and a warning is logged once.
+#[derive(Default)]
+pub struct SplitTimestamps {
+	// The duration of each frame as (ticks, time_scale), from the SPS VUI.
+	frame_duration: Option<(u64, u64)>,
+
+	// The timestamp of the current buffer and the number of access units split from it so far.
+	current: Option<(Timestamp, u64)>,
+
+	warned: bool,
+}
+
+impl SplitTimestamps {
+	/// Set the duration of each frame as (ticks, time_scale), or [None] if unknown.
+	pub fn set_frame_duration(&mut self, frame_duration: Option<(u64, u64)>) {
+		self.frame_duration = frame_duration;
+	}
+
+	/// Start splitting a buffer with the given timestamp.
+	pub fn start(&mut self, pts: Timestamp) {
+		self.current = Some((pts, 0));
+	}
+
+	/// Stop splitting the buffer, so any timestamps are used as is.
+	pub fn finish(&mut self) {
+		self.current = None;
+	}
+
+	/// Return the timestamp of the next access unit.
+	pub fn next(&mut self, pts: Timestamp) -> anyhow::Result<Timestamp> {
+		let Some((base, count)) = &mut self.current else {
+			return Ok(pts);
+		};
+
+		// Only access units split from the current buffer are stepped.
+		if *base != pts {
+			return Ok(pts);
+		}
+
+		let index = *count;
+		*count += 1;
+
+		if index == 0 {
+			return Ok(pts);
+		}
+
+		match self.frame_duration {
+			Some((ticks, time_scale)) => Ok(pts.checked_add(Timestamp::from_scale(ticks * index, time_scale)?)?),
+			None => {
+				if !self.warned {
+					tracing::warn!(
+						?pts,
+						"buffer contains multiple access units without SPS timing info, using the same timestamp"
+					);
+					self.warned = true;
+				}
+
+				Ok(pts)
+			}
+		}
+	}
+}
+
 // Return the size of the start code at the start of the buffer.
 pub fn after_start_code(b: &[u8]) -> anyhow::Result<Option<usize>> {
 	if b.len() < 3 {
@@ -517,6 +659,34 @@ mod tests {
 		assert_eq!(idr.as_ref(), &[0x65, 0x88, 0x84]);
 	}
 
+	#[test]
+	fn test_nal_iterator_error_offset() {
+		let mut data = Bytes::from(vec![0, 0, 2, 0x67]);
+		let mut iter = NalIterator::new(&mut data).with_offset(1234);
+
+		let err = iter.next().unwrap().unwrap_err();
+		let err = err.downcast_ref::<AnnexBError>().expect("missing offset");
+		assert_eq!(err.offset, 1234);
+		assert_eq!(err.bytes.as_ref(), &[0, 0, 2, 0x67]);
+	}
+
+	#[test]
+	fn test_nal_iterator_tracks_offset() {
+		let mut data = Bytes::from(vec![
+			0, 0, 0, 1, 0x67, 0x42, // First NAL
+			0, 0, 1, 0x68, 0xce, // Second NAL
+			0, 0, 0, 1,
+		]);
+		let mut iter = NalIterator::new(&mut data).with_offset(100);
+
+		iter.next().unwrap().unwrap();
+		assert_eq!(iter.offset(), 106);
+
+		iter.next().unwrap().unwrap();
+		assert_eq!(iter.offset(), 111);
+		assert!(iter.next().is_none());
+	}
+
 	#[test]
 	fn test_flush_empty_final_nal() {
 		// Edge case: final NAL is empty (just a start code with no data)
@@ -534,4 +704,44 @@ mod tests {
 		let final_nal = iter.flush().unwrap().unwrap();
 		assert_eq!(final_nal.len(), 0);
 	}
+
+	// A NAL unit of the given size filled with non-zero bytes, followed by the next start code.
+	fn large_nal(size: usize) -> Bytes {
+		let mut data = vec![0, 0, 0, 1];
+		data.resize(4 + size, 0xab);
+		data.extend([0, 0, 0, 1]);
+		Bytes::from(data)
+	}
+
+	#[test]
+	fn test_nal_iterator_max_size() {
+		let mut data = large_nal(1024);
+		let mut iter = NalIterator::new(&mut data).with_max_size(1023);
+
+		let err = iter.next().unwrap().unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(hang::Error::InvalidFrame)));
+	}
+
+	#[test]
+	fn test_nal_iterator_under_max_size() {
+		let mut data = large_nal(1024 * 1024);
+		let mut iter = NalIterator::new(&mut data).with_max_size(1024 * 1024);
+
+		assert_eq!(iter.next().unwrap().unwrap().len(), 1024 * 1024);
+		assert!(iter.next().is_none());
+	}
+
+	#[test]
+	fn test_nal_iterator_max_size_without_boundary() {
+		// No trailing start code, so the NAL unit would otherwise be buffered until one arrives.
+		let mut data = large_nal(1024).slice(..1028);
+		let mut iter = NalIterator::new(&mut data).with_max_size(1023);
+
+		let err = iter.next().unwrap().unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(hang::Error::InvalidFrame)));
+
+		let mut data = large_nal(1024).slice(..1028);
+		let iter = NalIterator::new(&mut data).with_max_size(1023);
+		assert!(iter.flush().is_err());
+	}
 }
@@ -0,0 +1,266 @@
+//! AudioSpecificConfig parsing for AAC (ISO 14496-3 1.6.2.1).
+//!
+//! SBR (HE-AAC) and PS (HE-AACv2) can be signaled in two ways:
+//! - explicitly, with an audioObjectType of 5 or 29 followed by the extension sample rate.
+//! - backwards compatible, with a sync extension appended after the core config.
+//!
+//! Either way, SBR changes the output sample rate, usually doubling it.
+
+use super::bits::BitReader;
+
+use anyhow::Context;
+
+const SAMPLE_RATES: [u32; 13] = [
+	96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+// Explicit sample rates above this are almost certainly a corrupt config.
+const MAX_SAMPLE_RATE: u32 = 192_000;
+
+// The syncExtensionType that signals SBR in a backwards compatible way.
+const SYNC_EXTENSION_SBR: u32 = 0x2b7;
+
+// The syncExtensionType that signals PS, following the SBR extension.
+const SYNC_EXTENSION_PS: u32 = 0x548;
+
+/// The parts of an AudioSpecificConfig needed to populate the catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioSpecificConfig {
+	/// The signaled audioObjectType, ex. 2 for AAC-LC or 5 for HE-AAC.
+	pub object_type: u8,
+
+	/// The sample rate of the core AAC decoder.
+	pub sample_rate: u32,
+
+	/// The channelConfiguration, where 0 means it's defined in a program_config_element.
+	pub channel_config: u8,
+
+	/// The output sample rate of the SBR extension, if present.
+	pub extension_sample_rate: Option<u32>,
+
+	/// Whether the PS extension is present, which upmixes a mono core to stereo.
+	pub ps: bool,
+
+	// The underlying object type when SBR/PS is signaled explicitly.
+	core_object_type: u8,
+}
+
+impl AudioSpecificConfig {
+	/// Parse a complete AudioSpecificConfig, including any backwards compatible SBR/PS extension.
+	///
+	/// The extension is only detected for object types we know how to skip; otherwise it's ignored.
+	pub fn parse(buf: &[u8]) -> anyhow::Result<Self> {
+		let mut r = BitReader::new(buf);
+		let mut config = Self::parse_core(&mut r)?;
+
+		match config.skip_specific_config(&mut r) {
+			Ok(()) => config.parse_sync_extension(&mut r)?,
+			Err(err) => tracing::debug!(%err, "unable to check for SBR/PS extensions"),
+		}
+
+		Ok(config)
+	}
+
+	/// Advance past an AudioSpecificConfig when the size is not signaled, as in LATM.
+	///
+	/// Backwards compatible extensions can't be detected without a size, so they're not supported.
+	pub fn skip(r: &mut BitReader) -> anyhow::Result<()> {
+		Self::parse_core(r)?.skip_specific_config(r)
+	}
+
+	/// The sample rate after decoding, including SBR.
+	pub fn output_sample_rate(&self) -> u32 {
+		self.extension_sample_rate.unwrap_or(self.sample_rate)
+	}
+
+	fn parse_core(r: &mut BitReader) -> anyhow::Result<Self> {
+		let object_type = audio_object_type(r)?;
+		let sample_rate = sampling_frequency(r)?;
+		let channel_config = r.read(4)? as u8;
+
+		let mut config = Self {
+			object_type,
+			sample_rate,
+			channel_config,
+			extension_sample_rate: None,
+			ps: false,
+			core_object_type: object_type,
+		};
+
+		// Explicit SBR/PS signaling, followed by the underlying object type.
+		if object_type == 5 || object_type == 29 {
+			config.ps = object_type == 29;
+			config.extension_sample_rate = Some(sampling_frequency(r)?);
+			config.core_object_type = audio_object_type(r)?;
+		}
+
+		Ok(config)
+	}
+
+	// Advance past the object type specific config, returning an error if the length can't be determined.
+	fn skip_specific_config(&self, r: &mut BitReader) -> anyhow::Result<()> {
+		let object_type = self.core_object_type;
+
+		match object_type {
+			1..=4 | 6 | 7 | 17 | 19..=23 => {
+				// GASpecificConfig
+				r.skip(1)?; // frameLengthFlag
+				if r.read_bool()? {
+					r.skip(14)?; // coreCoderDelay
+				}
+				let extension_flag = r.read_bool()?;
+				anyhow::ensure!(self.channel_config != 0, "program_config_element is not supported");
+
+				if object_type == 6 || object_type == 20 {
+					r.skip(3)?; // layerNr
+				}
+
+				if extension_flag {
+					if object_type == 22 {
+						r.skip(16)?; // numOfSubFrame, layer_length
+					}
+					if matches!(object_type, 17 | 19 | 20 | 23) {
+						r.skip(3)?; // resilience flags
+					}
+					r.skip(1)?; // extensionFlag3
+				}
+			}
+			_ => anyhow::bail!("unsupported AAC object type: {object_type}"),
+		}
+
+		if matches!(object_type, 17 | 19..=23) {
+			let ep_config = r.read(2)?;
+			anyhow::ensure!(ep_config < 2, "unsupported epConfig: {ep_config}");
+		}
+
+		Ok(())
+	}
+
+	// Parse the backwards compatible SBR/PS signaling appended to the core config.
+	fn parse_sync_extension(&mut self, r: &mut BitReader) -> anyhow::Result<()> {
+		// Explicit signaling takes precedence.
+		if self.extension_sample_rate.is_some() || r.remaining() < 16 {
+			return Ok(());
+		}
+
+		if r.read(11)? != SYNC_EXTENSION_SBR || audio_object_type(r)? != 5 || !r.read_bool()? {
+			return Ok(());
+		}
+
+		self.extension_sample_rate = Some(sampling_frequency(r)?);
+
+		if r.remaining() >= 12 && r.read(11)? == SYNC_EXTENSION_PS {
+			self.ps = r.read_bool()?;
+		}
+
+		Ok(())
+	}
+}
+
+fn audio_object_type(r: &mut BitReader) -> anyhow::Result<u8> {
+	match r.read(5)? {
+		31 => Ok(32 + r.read(6)? as u8),
+		object_type => Ok(object_type as u8),
+	}
+}
+
+fn sampling_frequency(r: &mut BitReader) -> anyhow::Result<u32> {
+	let sample_rate = match r.read(4)? {
+		// An explicit 24-bit sample rate, which is not byte aligned.
+		15 => r.read(24)?,
+		index => SAMPLE_RATES
+			.get(index as usize)
+			.copied()
+			.context("unsupported sample rate index")?,
+	};
+
+	if sample_rate == 0 || sample_rate > MAX_SAMPLE_RATE {
+		return Err(hang::Error::InvalidCodec).with_context(|| format!("invalid explicit sample rate: {sample_rate}"));
+	}
+
+	Ok(sample_rate)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn aac_lc() {
+		// AAC-LC, 44.1kHz, stereo
+		let config = AudioSpecificConfig::parse(&[0x12, 0x10]).unwrap();
+		assert_eq!(config.object_type, 2);
+		assert_eq!(config.output_sample_rate(), 44100);
+		assert_eq!(config.channel_config, 2);
+		assert!(!config.ps);
+	}
+
+	#[test]
+	fn explicit_sbr() {
+		// HE-AAC, 24kHz core, 48kHz SBR, stereo, AAC-LC core
+		let config = AudioSpecificConfig::parse(&[0x2b, 0x11, 0x88, 0x00]).unwrap();
+		assert_eq!(config.object_type, 5);
+		assert_eq!(config.sample_rate, 24000);
+		assert_eq!(config.output_sample_rate(), 48000);
+		assert!(!config.ps);
+	}
+
+	#[test]
+	fn explicit_ps() {
+		// HE-AACv2, 24kHz core, 48kHz SBR, mono core, AAC-LC core
+		let config = AudioSpecificConfig::parse(&[0xeb, 0x09, 0x88, 0x00]).unwrap();
+		assert_eq!(config.object_type, 29);
+		assert_eq!(config.output_sample_rate(), 48000);
+		assert_eq!(config.channel_config, 1);
+		assert!(config.ps);
+	}
+
+	#[test]
+	fn backwards_compatible_sbr_ps() {
+		// AAC-LC, 24kHz core, mono, followed by the SBR (48kHz) and PS sync extensions.
+		let config = AudioSpecificConfig::parse(&[0x13, 0x08, 0x56, 0xe5, 0x9d, 0x48, 0x80]).unwrap();
+		assert_eq!(config.object_type, 2);
+		assert_eq!(config.sample_rate, 24000);
+		assert_eq!(config.output_sample_rate(), 48000);
+		assert!(config.ps);
+	}
+
+	#[test]
+	fn explicit_sample_rate() {
+		// AAC-LC, explicit 88.2kHz, stereo
+		let config = AudioSpecificConfig::parse(&[0x17, 0x80, 0xac, 0x44, 0x10]).unwrap();
+		assert_eq!(config.sample_rate, 88200);
+		assert_eq!(config.channel_config, 2);
+	}
+
+	#[test]
+	fn explicit_sample_rate_after_extended_object_type() {
+		// USAC (extended object type 42), explicit 88.2kHz, stereo
+		let config = AudioSpecificConfig::parse(&[0xf9, 0x5e, 0x02, 0xb1, 0x10, 0x40]).unwrap();
+		assert_eq!(config.object_type, 42);
+		assert_eq!(config.sample_rate, 88200);
+		assert_eq!(config.channel_config, 2);
+	}
+
+	#[test]
+	fn explicit_sample_rate_zero() {
+		// AAC-LC, explicit 0Hz, stereo
+		let err = AudioSpecificConfig::parse(&[0x17, 0x80, 0x00, 0x00, 0x10]).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(hang::Error::InvalidCodec)));
+	}
+
+	#[test]
+	fn explicit_sample_rate_too_high() {
+		// AAC-LC, explicit 16.7MHz, stereo
+		let err = AudioSpecificConfig::parse(&[0x17, 0xff, 0xff, 0xff, 0x90]).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(hang::Error::InvalidCodec)));
+	}
+
+	#[test]
+	fn trailing_padding() {
+		// Some encoders pad the config with zeros, which is not a sync extension.
+		let config = AudioSpecificConfig::parse(&[0x12, 0x10, 0x00, 0x00]).unwrap();
+		assert_eq!(config.output_sample_rate(), 44100);
+		assert_eq!(config.extension_sample_rate, None);
+	}
+}
@@ -1,4 +1,7 @@
-use super::annexb::{NalIterator, START_CODE};
+use super::annexb::{NalIterator, START_CODE, SplitTimestamps};
+use super::bits::BitReader;
+use super::output::{Output, TrackConfig};
+use super::{DecoderBuf, ImportOptions, Importer, MediaDecoder, MediaInfo, SeekIndex, ensure_frame_size};
 
 use anyhow::Context;
 use buf_list::BufList;
@@ -7,14 +10,8 @@ use tokio::io::{AsyncRead, AsyncReadExt};
 
 /// A decoder for H.264 with inline SPS/PPS.
 pub struct Avc3 {
-	// The broadcast being produced.
-	broadcast: moq_lite::BroadcastProducer,
-
-	// The catalog being produced.
-	catalog: hang::catalog::CatalogProducer,
-
-	// The track being produced.
-	track: Option<hang::container::OrderedProducer>,
+	// The track being produced, and the options.
+	output: Output,
 
 	// Whether the track has been initialized.
 	// If it changes, then we'll reinitialize with a new track.
@@ -23,22 +20,59 @@ pub struct Avc3 {
 	// The current frame being built.
 	current: Frame,
 
+	// Steps the timestamp of each access unit split from a single buffer.
+	split: SplitTimestamps,
+
 	// Used to compute wall clock timestamps if needed.
 	zero: Option<tokio::time::Instant>,
+
+	// The number of bytes consumed from the stream, used to report the location of errors.
+	offset: u64,
 }
 
 impl Avc3 {
 	pub fn new(broadcast: moq_lite::BroadcastProducer, catalog: hang::catalog::CatalogProducer) -> Self {
 		Self {
-			broadcast,
-			catalog,
-			track: None,
+			output: Output::new(broadcast, catalog),
 			config: None,
 			current: Default::default(),
+			split: Default::default(),
 			zero: None,
+			offset: 0,
 		}
 	}
 
+	/// Advertise a fixed jitter in the catalog, used by the player to size its jitter buffer.
+	///
+	/// This only applies to tracks created after this call.
+	pub fn set_jitter(&mut self, jitter: Option<std::time::Duration>) {
+		self.output.set_jitter(jitter);
+	}
+
+	/// The options used by this importer.
+	pub fn options(&self) -> &ImportOptions {
+		self.output.options()
+	}
+
+	/// Replace every option at once, see [ImportOptions].
+	///
+	/// This only applies to tracks created after this call, so call it before decoding.
+	pub fn set_options(&mut self, options: ImportOptions) {
+		self.output.set_options(options);
+	}
+
+	/// The seek index for the current track, or [None] if not enabled.
+	///
+	/// The index is reset when the SPS changes and a new track is created.
+	pub fn seek_index(&self) -> Option<&SeekIndex> {
+		self.output.seek_index()
+	}
+
+	/// The media seen so far in dry run mode, or [None] if not enabled.
+	pub fn media_info(&self) -> Option<&MediaInfo> {
+		self.output.media_info()
+	}
+
 	fn init(&mut self, sps: &h264_parser::Sps) -> anyhow::Result<()> {
 		let constraint_flags: u8 = ((sps.constraint_set0_flag as u8) << 7)
 			| ((sps.constraint_set1_flag as u8) << 6)
@@ -65,7 +99,7 @@ impl Avc3 {
 			display_ratio_height: None,
 			optimize_for_latency: None,
 			container: hang::catalog::Container::Legacy,
-			jitter: None,
+			jitter: self.output.jitter()?,
 		};
 
 		if let Some(old) = &self.config
@@ -74,27 +108,18 @@ impl Avc3 {
 			return Ok(());
 		}
 
-		let mut catalog = self.catalog.lock();
-
-		if let Some(track) = &self.track.take() {
-			tracing::debug!(name = ?track.info.name, "reinitializing track");
-			catalog.video.remove_track(&track.info);
-		}
-
-		let track = catalog.video.create_track("avc3", config.clone());
-		tracing::debug!(name = ?track.name, ?config, "starting track");
-
-		let track = self.broadcast.create_track(track);
-
+		self.output.replace_track("avc3", TrackConfig::Video(config.clone()));
 		self.config = Some(config);
-		self.track = Some(track.into());
 
 		Ok(())
 	}
 
 	/// Initialize the decoder with SPS/PPS and other non-slice NALs.
 	pub fn initialize<T: Buf + AsRef<[u8]>>(&mut self, buf: &mut T) -> anyhow::Result<()> {
-		let mut nals = NalIterator::new(buf);
+		let remaining = buf.remaining();
+		let mut nals = NalIterator::new(buf)
+			.with_offset(self.offset)
+			.with_max_size(self.output.max_frame_size());
 
 		while let Some(nal) = nals.next().transpose()? {
 			self.decode_nal(nal, None)?;
@@ -104,6 +129,8 @@ impl Avc3 {
 			self.decode_nal(nal, None)?;
 		}
 
+		self.offset += remaining as u64;
+
 		Ok(())
 	}
 
@@ -131,17 +158,26 @@ impl Avc3 {
 		let pts = self.pts(pts)?;
 
 		// Iterate over the NAL units in the buffer based on start codes.
-		let nals = NalIterator::new(buf);
+		let mut nals = NalIterator::new(buf)
+			.with_offset(self.offset)
+			.with_max_size(self.output.max_frame_size());
 
-		for nal in nals {
-			self.decode_nal(nal?, Some(pts))?;
+		while let Some(nal) = nals.next().transpose()? {
+			self.decode_nal(nal, Some(pts))?;
 		}
 
+		self.offset = nals.offset();
+
 		Ok(())
 	}
 
 	/// Decode all data in the buffer, assuming the buffer contains (the rest of) a frame.
 	///
+	/// If the buffer contains multiple access units, they are split into separate frames using
+	/// the AUD NAL or the first slice of each picture. The first frame uses the given timestamp,
+	/// and each frame after it is stepped by the frame duration from the SPS timing info.
+	/// If the SPS has no timing info, every frame uses the same timestamp and a warning is logged.
+	///
 	/// Unlike [Self::decode_stream], this is called when we know NAL boundaries.
 	/// This can avoid a frame of latency just waiting for the next frame's start code.
 	/// This can also be used when EOF is detected to flush the final frame.
@@ -153,8 +189,13 @@ impl Avc3 {
 		pts: Option<hang::container::Timestamp>,
 	) -> anyhow::Result<()> {
 		let pts = self.pts(pts)?;
+		let remaining = buf.remaining();
+		self.split.start(pts);
+
 		// Iterate over the NAL units in the buffer based on start codes.
-		let mut nals = NalIterator::new(buf);
+		let mut nals = NalIterator::new(buf)
+			.with_offset(self.offset)
+			.with_max_size(self.output.max_frame_size());
 
 		// Iterate over each NAL that is followed by a start code.
 		while let Some(nal) = nals.next().transpose()? {
@@ -166,8 +207,11 @@ impl Avc3 {
 			self.decode_nal(nal, Some(pts))?;
 		}
 
+		self.offset += remaining as u64;
+
 		// Flush the frame if we read a slice.
 		self.maybe_start_frame(Some(pts))?;
+		self.split.finish();
 
 		Ok(())
 	}
@@ -188,12 +232,25 @@ impl Avc3 {
 				let nal = h264_parser::nal::ebsp_to_rbsp(&nal[1..]);
 				let sps = h264_parser::Sps::parse(&nal)?;
 				self.init(&sps)?;
+
+				// The timing info is optional, so a VUI we can't parse is ignored.
+				let frame_duration = frame_duration(&nal).unwrap_or_else(|err| {
+					tracing::debug!(%err, "failed to parse the SPS timing info");
+					None
+				});
+				self.split.set_frame_duration(frame_duration);
 			}
 			// TODO parse the SPS again and reinitialize the track if needed
 			Some(NalType::Aud) | Some(NalType::Pps) | Some(NalType::Sei) => {
 				self.maybe_start_frame(pts)?;
 			}
 			Some(NalType::IdrSlice) => {
+				// first_mb_in_slice == 0, means this is the first slice of a new picture.
+				// This splits back-to-back IDR pictures without an AUD.
+				if nal.get(1).context("NAL unit is too short")? & 0x80 != 0 {
+					self.maybe_start_frame(pts)?;
+				}
+
 				self.current.contains_idr = true;
 				self.current.contains_slice = true;
 			}
@@ -216,6 +273,9 @@ impl Avc3 {
 		// Rather than keeping the original size of the start code, we replace it with a 4 byte start code.
 		// It's just marginally easier and potentially more efficient down the line (JS player with MSE).
 		// NOTE: This is ref-counted and static, so it's extremely cheap to clone.
+		let size = self.current.chunks.remaining() + START_CODE.len() + nal.len();
+		ensure_frame_size(size, self.output.max_frame_size())?;
+
 		self.current.chunks.push_chunk(START_CODE.clone());
 		self.current.chunks.push_chunk(nal);
 
@@ -228,8 +288,8 @@ impl Avc3 {
 			return Ok(());
 		}
 
-		let track = self.track.as_mut().context("expected SPS before any frames")?;
-		let pts = pts.context("missing timestamp")?;
+		anyhow::ensure!(self.output.is_initialized(), "expected SPS before any frames");
+		let pts = self.split.next(pts.context("missing timestamp")?)?;
 
 		let payload = std::mem::take(&mut self.current.chunks);
 		let frame = hang::container::Frame {
@@ -238,7 +298,7 @@ impl Avc3 {
 			payload,
 		};
 
-		track.write(frame)?;
+		self.output.write(frame)?;
 
 		self.current.contains_idr = false;
 		self.current.contains_slice = false;
@@ -247,7 +307,21 @@ impl Avc3 {
 	}
 
 	pub fn is_initialized(&self) -> bool {
-		self.track.is_some()
+		self.output.is_initialized()
+	}
+
+	/// The name of the track in the broadcast, or [None] if not initialized yet.
+	///
+	/// A new track is created if the SPS changes, so this may change after each decode.
+	pub fn track_name(&self) -> Option<&str> {
+		self.output.track_name()
+	}
+
+	/// The track being produced, or [None] if not initialized yet.
+	///
+	/// This can be used to subscribe to the track locally via [hang::container::OrderedProducer::consume].
+	pub fn track(&self) -> Option<&hang::container::OrderedProducer> {
+		self.output.track()
 	}
 
 	fn pts(&mut self, hint: Option<hang::container::Timestamp>) -> anyhow::Result<hang::container::Timestamp> {
@@ -262,12 +336,51 @@ impl Avc3 {
 	}
 }
 
-impl Drop for Avc3 {
-	fn drop(&mut self) {
-		if let Some(track) = self.track.take() {
-			tracing::debug!(name = ?track.info.name, "ending track");
-			self.catalog.lock().video.remove_track(&track.info);
-		}
+impl MediaDecoder for Avc3 {
+	fn initialize(&mut self, mut buf: &mut dyn DecoderBuf) -> anyhow::Result<()> {
+		Avc3::initialize(self, &mut buf)
+	}
+
+	fn decode_frame(
+		&mut self,
+		mut buf: &mut dyn DecoderBuf,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		Avc3::decode_frame(self, &mut buf, pts)
+	}
+
+	fn decode_stream(
+		&mut self,
+		mut buf: &mut dyn DecoderBuf,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		Avc3::decode_stream(self, &mut buf, pts)
+	}
+
+	fn is_initialized(&self) -> bool {
+		Avc3::is_initialized(self)
+	}
+
+	fn track_name(&self) -> Option<&str> {
+		Avc3::track_name(self)
+	}
+}
+
+impl Importer for Avc3 {
+	fn options(&self) -> &ImportOptions {
+		Avc3::options(self)
+	}
+
+	fn set_options(&mut self, options: ImportOptions) {
+		Avc3::set_options(self, options)
+	}
+
+	fn seek_index(&self) -> Option<&SeekIndex> {
+		Avc3::seek_index(self)
+	}
+
+	fn media_info(&self) -> Option<&MediaInfo> {
+		Avc3::media_info(self)
 	}
 }
 
@@ -299,3 +412,324 @@ struct Frame {
 	contains_idr: bool,
 	contains_slice: bool,
 }
+
+// Parse the frame duration as (ticks, time_scale) from the VUI timing info in the SPS, if present.
+//
+// The h264 parser stops at the VUI, so every field before it is skipped again.
+fn frame_duration(rbsp: &[u8]) -> anyhow::Result<Option<(u64, u64)>> {
+	let mut r = BitReader::new(rbsp);
+
+	let profile_idc = r.read(8)?;
+	r.skip(16)?; // constraint_set_flags, level_idc
+	r.read_ue()?; // seq_parameter_set_id
+
+	if matches!(
+		profile_idc,
+		100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+	) {
+		let chroma_format_idc = r.read_ue()?;
+		if chroma_format_idc == 3 {
+			r.skip(1)?; // separate_colour_plane_flag
+		}
+
+		r.read_ue()?; // bit_depth_luma_minus8
+		r.read_ue()?; // bit_depth_chroma_minus8
+		r.skip(1)?; // qpprime_y_zero_transform_bypass_flag
+
+		// seq_scaling_matrix_present_flag
+		if r.read_bool()? {
+			let count = if chroma_format_idc == 3 { 12 } else { 8 };
+			for i in 0..count {
+				if r.read_bool()? {
+					skip_scaling_list(&mut r, if i < 6 { 16 } else { 64 })?;
+				}
+			}
+		}
+	}
+
+	r.read_ue()?; // log2_max_frame_num_minus4
+
+	match r.read_ue()? {
+		0 => {
+			r.read_ue()?; // log2_max_pic_order_cnt_lsb_minus4
+		}
+		1 => {
+			r.skip(1)?; // delta_pic_order_always_zero_flag
+			r.read_se()?; // offset_for_non_ref_pic
+			r.read_se()?; // offset_for_top_to_bottom_field
+
+			for _ in 0..r.read_ue()? {
+				r.read_se()?; // offset_for_ref_frame
+			}
+		}
+		_ => {}
+	}
+
+	r.read_ue()?; // max_num_ref_frames
+	r.skip(1)?; // gaps_in_frame_num_value_allowed_flag
+	r.read_ue()?; // pic_width_in_mbs_minus1
+	r.read_ue()?; // pic_height_in_map_units_minus1
+
+	// frame_mbs_only_flag
+	if !r.read_bool()? {
+		r.skip(1)?; // mb_adaptive_frame_field_flag
+	}
+
+	r.skip(1)?; // direct_8x8_inference_flag
+
+	// frame_cropping_flag
+	if r.read_bool()? {
+		for _ in 0..4 {
+			r.read_ue()?; // frame_crop_*_offset
+		}
+	}
+
+	// vui_parameters_present_flag
+	if !r.read_bool()? {
+		return Ok(None);
+	}
+
+	// aspect_ratio_info_present_flag
+	if r.read_bool()? && r.read(8)? == 255 {
+		r.skip(32)?; // sar_width, sar_height
+	}
+
+	// overscan_info_present_flag
+	if r.read_bool()? {
+		r.skip(1)?; // overscan_appropriate_flag
+	}
+
+	// video_signal_type_present_flag
+	if r.read_bool()? {
+		r.skip(4)?; // video_format, video_full_range_flag
+
+		// colour_description_present_flag
+		if r.read_bool()? {
+			r.skip(24)?; // colour_primaries, transfer_characteristics, matrix_coefficients
+		}
+	}
+
+	// chroma_loc_info_present_flag
+	if r.read_bool()? {
+		r.read_ue()?; // chroma_sample_loc_type_top_field
+		r.read_ue()?; // chroma_sample_loc_type_bottom_field
+	}
+
+	// timing_info_present_flag
+	if !r.read_bool()? {
+		return Ok(None);
+	}
+
+	let num_units_in_tick = r.read(32)? as u64;
+	let time_scale = r.read(32)? as u64;
+	if num_units_in_tick == 0 || time_scale == 0 {
+		return Ok(None);
+	}
+
+	// A frame is two ticks, one for each field.
+	Ok(Some((2 * num_units_in_tick, time_scale)))
+}
+
+fn skip_scaling_list(r: &mut BitReader, size: usize) -> anyhow::Result<()> {
+	let mut last_scale = 8;
+	let mut next_scale = 8;
+
+	for _ in 0..size {
+		if next_scale != 0 {
+			next_scale = (last_scale + r.read_se()? + 256) % 256;
+		}
+
+		if next_scale != 0 {
+			last_scale = next_scale;
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::import::AnnexBError;
+
+	// Baseline profile, 320x240
+	const SPS: &[u8] = &[0, 0, 0, 1, 0x67, 0x42, 0xc0, 0x1e, 0xda, 0x05, 0x07, 0xe4];
+	// The same SPS with VUI timing info, 29.97 fps.
+	const SPS_TIMING: &[u8] = &[
+		0, 0, 0, 1, 0x67, 0x42, 0xc0, 0x1e, 0xda, 0x05, 0x07, 0xe8, 0x40, 0x00, 0x00, 0xfa, 0x40, 0x00, 0x3a, 0x98,
+		0x21,
+	];
+	const PPS: &[u8] = &[0, 0, 0, 1, 0x68, 0xce, 0x3c, 0x80];
+	const AUD: &[u8] = &[0, 0, 0, 1, 0x09, 0xf0];
+	const IDR: &[u8] = &[0, 0, 0, 1, 0x65, 0x88, 0x84, 0x00];
+
+	fn setup() -> (Avc3, hang::container::OrderedConsumer) {
+		setup_with(SPS)
+	}
+
+	fn setup_with(sps: &[u8]) -> (Avc3, hang::container::OrderedConsumer) {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut avc3 = Avc3::new(broadcast, catalog);
+
+		let mut init = Bytes::from([sps, PPS].concat());
+		avc3.initialize(&mut init).unwrap();
+
+		let consumer = avc3.track().unwrap().consume(std::time::Duration::from_secs(1));
+		(avc3, consumer)
+	}
+
+	// Assert the next frame starts a new group with the expected size.
+	async fn assert_keyframe(consumer: &mut hang::container::OrderedConsumer, size: usize) {
+		let frame = consumer.read().await.unwrap().unwrap();
+		assert!(frame.keyframe, "expected a new group per access unit");
+		assert_eq!(frame.payload.num_bytes(), size);
+	}
+
+	#[tokio::test]
+	async fn decode_frame_splits_on_aud() {
+		let (mut avc3, mut consumer) = setup();
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+
+		let mut buf = Bytes::from([AUD, IDR, AUD, IDR].concat());
+		avc3.decode_frame(&mut buf, Some(pts)).unwrap();
+
+		// The SPS/PPS from initialize are prepended to the first frame.
+		assert_keyframe(&mut consumer, SPS.len() + PPS.len() + AUD.len() + IDR.len()).await;
+		assert_keyframe(&mut consumer, AUD.len() + IDR.len()).await;
+	}
+
+	#[tokio::test]
+	async fn decode_frame_splits_on_first_slice() {
+		let (mut avc3, mut consumer) = setup();
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+
+		// The first access unit has an AUD but the second doesn't.
+		let mut buf = Bytes::from([AUD, IDR, IDR].concat());
+		avc3.decode_frame(&mut buf, Some(pts)).unwrap();
+
+		assert_keyframe(&mut consumer, SPS.len() + PPS.len() + AUD.len() + IDR.len()).await;
+		assert_keyframe(&mut consumer, IDR.len()).await;
+	}
+
+	#[test]
+	fn parse_frame_duration() {
+		let rbsp = h264_parser::nal::ebsp_to_rbsp(&SPS_TIMING[5..]);
+		assert_eq!(frame_duration(&rbsp).unwrap(), Some((2002, 60000)));
+
+		let rbsp = h264_parser::nal::ebsp_to_rbsp(&SPS[5..]);
+		assert_eq!(frame_duration(&rbsp).unwrap(), None);
+	}
+
+	#[tokio::test]
+	async fn decode_frame_steps_split_timestamps() {
+		let (mut avc3, mut consumer) = setup_with(SPS_TIMING);
+		let pts = hang::container::Timestamp::from_secs(1).unwrap();
+
+		let mut buf = Bytes::from([AUD, IDR, AUD, IDR, AUD, IDR].concat());
+		avc3.decode_frame(&mut buf, Some(pts)).unwrap();
+
+		for i in 0..3 {
+			let frame = consumer.read().await.unwrap().unwrap();
+			let step = hang::container::Timestamp::from_scale(2002 * i, 60000).unwrap();
+			assert_eq!(frame.timestamp, pts + step);
+		}
+
+		// The next buffer starts over at its own timestamp.
+		let pts = hang::container::Timestamp::from_secs(2).unwrap();
+		let mut buf = Bytes::from([AUD, IDR].concat());
+		avc3.decode_frame(&mut buf, Some(pts)).unwrap();
+		assert_eq!(consumer.read().await.unwrap().unwrap().timestamp, pts);
+	}
+
+	#[tokio::test]
+	async fn decode_frame_without_timing_reuses_timestamp() {
+		let (mut avc3, mut consumer) = setup();
+		let pts = hang::container::Timestamp::from_secs(1).unwrap();
+
+		let mut buf = Bytes::from([AUD, IDR, AUD, IDR].concat());
+		avc3.decode_frame(&mut buf, Some(pts)).unwrap();
+
+		for _ in 0..2 {
+			assert_eq!(consumer.read().await.unwrap().unwrap().timestamp, pts);
+		}
+	}
+
+	#[tokio::test]
+	async fn seek_index_records_keyframes() {
+		const SLICE: &[u8] = &[0, 0, 0, 1, 0x41, 0x9a, 0x00];
+
+		let (mut avc3, mut consumer) = setup();
+		avc3.set_options(ImportOptions {
+			seek_index: Some(SeekIndex::new()),
+			..Default::default()
+		});
+
+		let ms = |ms: u64| hang::container::Timestamp::from_micros(ms * 1_000).unwrap();
+
+		for (pts, slice) in [(0, IDR), (33, SLICE), (66, IDR), (100, SLICE)] {
+			let mut buf = Bytes::from([AUD, slice].concat());
+			avc3.decode_frame(&mut buf, Some(ms(pts))).unwrap();
+		}
+
+		let mut keyframes = Vec::new();
+		for _ in 0..4 {
+			let frame = consumer.read().await.unwrap().unwrap();
+			if frame.keyframe {
+				keyframes.push(frame.timestamp);
+			}
+		}
+
+		let index = avc3.seek_index().unwrap();
+		let points: Vec<_> = index.points().map(|point| (point.timestamp, point.group)).collect();
+		assert_eq!(points, [(keyframes[0], 0), (keyframes[1], 1)]);
+		assert_eq!(keyframes, [ms(0), ms(66)]);
+
+		assert_eq!(index.seek(ms(50)).unwrap().group, 0);
+		assert_eq!(index.seek(ms(100)).unwrap().group, 1);
+	}
+
+	#[test]
+	fn error_reports_stream_offset() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut avc3 = Avc3::new(broadcast, catalog);
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+
+		// A valid prefix containing an AUD and SEI, which don't require an SPS.
+		let mut valid = Bytes::from_static(&[0, 0, 0, 1, 0x09, 0xf0, 0, 0, 1, 0x06, 0x05, 0x00]);
+		avc3.decode_frame(&mut valid, Some(pts)).unwrap();
+
+		// Followed by garbage where a start code is expected.
+		let mut corrupt = Bytes::from_static(&[0, 0, 2, 0x65, 0x88]);
+		let err = avc3.decode_frame(&mut corrupt, Some(pts)).unwrap_err();
+
+		let err = err.downcast_ref::<AnnexBError>().expect("missing offset");
+		assert_eq!(err.offset, 12);
+		assert_eq!(err.bytes.as_ref(), &[0, 0, 2, 0x65]);
+	}
+
+	#[test]
+	fn dry_run() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+		let mut avc3 = Avc3::new(broadcast, catalog.clone());
+		avc3.set_options(ImportOptions {
+			dry_run: true,
+			..Default::default()
+		});
+
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+		let mut buf = Bytes::from([SPS, PPS, AUD, IDR, AUD, IDR].concat());
+		avc3.decode_frame(&mut buf, Some(pts)).unwrap();
+
+		// Nothing was published.
+		assert!(avc3.is_initialized());
+		assert!(avc3.track().is_none());
+		assert!(catalog.lock().video.renditions.is_empty());
+
+		let info = avc3.media_info().unwrap();
+		assert_eq!(info.video[0].coded_width, Some(320));
+		assert_eq!(info.frames, 2);
+	}
+}
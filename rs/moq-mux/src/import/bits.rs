@@ -0,0 +1,100 @@
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// A big-endian bit reader, used for AAC and H.264 fields that are not byte aligned.
+pub struct BitReader<'a> {
+	data: &'a [u8],
+	position: usize,
+}
+
+impl<'a> BitReader<'a> {
+	pub fn new(data: &'a [u8]) -> Self {
+		Self { data, position: 0 }
+	}
+
+	/// The number of bits read so far.
+	#[cfg_attr(not(feature = "aac"), allow(dead_code))]
+	pub fn position(&self) -> usize {
+		self.position
+	}
+
+	/// The number of bits left to read.
+	pub fn remaining(&self) -> usize {
+		self.data.len() * 8 - self.position
+	}
+
+	pub fn skip(&mut self, bits: usize) -> anyhow::Result<()> {
+		anyhow::ensure!(bits <= self.remaining(), "bitstream is truncated");
+		self.position += bits;
+		Ok(())
+	}
+
+	pub fn read(&mut self, bits: usize) -> anyhow::Result<u32> {
+		debug_assert!(bits <= 32);
+		anyhow::ensure!(bits <= self.remaining(), "bitstream is truncated");
+
+		let mut value = 0;
+		for _ in 0..bits {
+			let bit = (self.data[self.position / 8] >> (7 - self.position % 8)) & 1;
+			value = (value << 1) | bit as u32;
+			self.position += 1;
+		}
+
+		Ok(value)
+	}
+
+	pub fn read_bool(&mut self) -> anyhow::Result<bool> {
+		Ok(self.read(1)? == 1)
+	}
+
+	/// Read an unsigned Exp-Golomb code, ue(v) in the H.264 spec.
+	#[cfg_attr(not(feature = "h264"), allow(dead_code))]
+	pub fn read_ue(&mut self) -> anyhow::Result<u32> {
+		let mut zeros = 0;
+		while !self.read_bool()? {
+			zeros += 1;
+			anyhow::ensure!(zeros < 32, "Exp-Golomb code is too long");
+		}
+
+		Ok(((1u64 << zeros) - 1 + self.read(zeros)? as u64) as u32)
+	}
+
+	/// Read a signed Exp-Golomb code, se(v) in the H.264 spec.
+	#[cfg_attr(not(feature = "h264"), allow(dead_code))]
+	pub fn read_se(&mut self) -> anyhow::Result<i32> {
+		let value = self.read_ue()? as i64;
+		Ok(if value % 2 == 1 { (value + 1) / 2 } else { -value / 2 } as i32)
+	}
+
+	#[cfg_attr(not(feature = "aac"), allow(dead_code))]
+	pub fn read_bytes(&mut self, size: usize) -> anyhow::Result<Bytes> {
+		anyhow::ensure!(size * 8 <= self.remaining(), "bitstream is truncated");
+		let bytes = self.slice(self.position, size * 8);
+		self.position += size * 8;
+		Ok(bytes)
+	}
+
+	/// Copy the given bit range into a byte aligned buffer, padded with zeros.
+	#[cfg_attr(not(feature = "aac"), allow(dead_code))]
+	#[allow(clippy::manual_is_multiple_of)] // is_multiple_of is unstable in Rust 1.85
+	pub fn slice(&self, start: usize, bits: usize) -> Bytes {
+		if start % 8 == 0 && bits % 8 == 0 {
+			return Bytes::copy_from_slice(&self.data[start / 8..(start + bits) / 8]);
+		}
+
+		let mut r = BitReader {
+			data: self.data,
+			position: start,
+		};
+
+		let mut out = BytesMut::with_capacity(bits.div_ceil(8));
+		let mut remaining = bits;
+		while remaining > 0 {
+			let size = remaining.min(8);
+			let byte = r.read(size).expect("range was already checked") << (8 - size);
+			out.put_u8(byte as u8);
+			remaining -= size;
+		}
+
+		out.freeze()
+	}
+}
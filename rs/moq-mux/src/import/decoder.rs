@@ -1,7 +1,35 @@
 use std::{fmt, str::FromStr};
 
+use anyhow::Context;
 use bytes::Buf;
 use hang::Error;
+use hang::container::Timestamp;
+
+use super::SeekIndex;
+
+/// The default maximum size of a frame, NAL unit, or fMP4 atom, see [ImportOptions::max_frame_size].
+///
+/// This guards against untrusted input declaring (or never terminating) an enormous frame.
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+// Return an error if the size exceeds the maximum, before anything is allocated.
+#[cfg_attr(
+	not(any(
+		feature = "h264",
+		feature = "mp4",
+		feature = "h265",
+		feature = "aac",
+		feature = "opus"
+	)),
+	allow(dead_code)
+)]
+pub(crate) fn ensure_frame_size(size: usize, max: usize) -> anyhow::Result<()> {
+	if size > max {
+		return Err(Error::InvalidFrame).with_context(|| format!("frame is too large: {size} > {max} bytes"));
+	}
+
+	Ok(())
+}
 
 /// The supported decoder formats.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -24,6 +52,28 @@ pub enum DecoderFormat {
 	Opus,
 }
 
+impl DecoderFormat {
+	/// The formats compiled in via feature flags.
+	pub fn supported() -> &'static [DecoderFormat] {
+		&[
+			#[cfg(feature = "h264")]
+			DecoderFormat::Avc3,
+			#[cfg(feature = "mp4")]
+			DecoderFormat::Fmp4,
+			#[cfg(feature = "h265")]
+			DecoderFormat::Hev1,
+			#[cfg(feature = "aac")]
+			DecoderFormat::Aac,
+			#[cfg(feature = "opus")]
+			DecoderFormat::Opus,
+		]
+	}
+}
+
+// Every format name regardless of feature flags, to distinguish a disabled format from an unknown one.
+const DECODER_FORMAT_NAMES: &[&str] = &["avc3", "h264", "annex-b", "hev1", "fmp4", "cmaf", "aac", "opus"];
+const STREAM_FORMAT_NAMES: &[&str] = &["avc3", "h264", "annex-b", "hev1", "fmp4", "cmaf", "aac", "loas", "latm"];
+
 impl FromStr for DecoderFormat {
 	type Err = Error;
 
@@ -44,14 +94,26 @@ impl FromStr for DecoderFormat {
 			"aac" => Ok(DecoderFormat::Aac),
 			#[cfg(feature = "opus")]
 			"opus" => Ok(DecoderFormat::Opus),
+			_ if DECODER_FORMAT_NAMES.contains(&s) => Err(Error::FormatNotEnabled(s.to_string())),
 			_ => Err(Error::UnknownFormat(s.to_string())),
 		}
 	}
 }
 
 impl fmt::Display for DecoderFormat {
+	#[cfg_attr(
+		not(any(
+			feature = "h264",
+			feature = "mp4",
+			feature = "h265",
+			feature = "aac",
+			feature = "opus"
+		)),
+		allow(unused_variables)
+	)]
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		match self {
+		// Dereference so the match is still exhaustive when no formats are enabled.
+		match *self {
 			#[cfg(feature = "h264")]
 			DecoderFormat::Avc3 => write!(f, "avc3"),
 			#[cfg(feature = "mp4")]
@@ -79,6 +141,25 @@ pub enum StreamFormat {
 	/// aka H265 with inline SPS/PPS
 	#[cfg(feature = "h265")]
 	Hev1,
+	/// AAC with LOAS/LATM framing.
+	#[cfg(feature = "aac")]
+	Aac,
+}
+
+impl StreamFormat {
+	/// The formats compiled in via feature flags.
+	pub fn supported() -> &'static [StreamFormat] {
+		&[
+			#[cfg(feature = "h264")]
+			StreamFormat::Avc3,
+			#[cfg(feature = "mp4")]
+			StreamFormat::Fmp4,
+			#[cfg(feature = "h265")]
+			StreamFormat::Hev1,
+			#[cfg(feature = "aac")]
+			StreamFormat::Aac,
+		]
+	}
 }
 
 impl FromStr for StreamFormat {
@@ -97,20 +178,30 @@ impl FromStr for StreamFormat {
 			"hev1" => Ok(StreamFormat::Hev1),
 			#[cfg(feature = "mp4")]
 			"fmp4" | "cmaf" => Ok(StreamFormat::Fmp4),
+			#[cfg(feature = "aac")]
+			"aac" | "loas" | "latm" => Ok(StreamFormat::Aac),
+			_ if STREAM_FORMAT_NAMES.contains(&s) => Err(Error::FormatNotEnabled(s.to_string())),
 			_ => Err(Error::UnknownFormat(s.to_string())),
 		}
 	}
 }
 
 impl fmt::Display for StreamFormat {
+	#[cfg_attr(
+		not(any(feature = "h264", feature = "mp4", feature = "h265", feature = "aac")),
+		allow(unused_variables)
+	)]
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		match self {
+		// Dereference so the match is still exhaustive when no formats are enabled.
+		match *self {
 			#[cfg(feature = "h264")]
 			StreamFormat::Avc3 => write!(f, "avc3"),
 			#[cfg(feature = "mp4")]
 			StreamFormat::Fmp4 => write!(f, "fmp4"),
 			#[cfg(feature = "h265")]
 			StreamFormat::Hev1 => write!(f, "hev1"),
+			#[cfg(feature = "aac")]
+			StreamFormat::Aac => write!(f, "aac"),
 		}
 	}
 }
@@ -124,78 +215,231 @@ impl From<StreamFormat> for DecoderFormat {
 			StreamFormat::Fmp4 => DecoderFormat::Fmp4,
 			#[cfg(feature = "h265")]
 			StreamFormat::Hev1 => DecoderFormat::Hev1,
+			#[cfg(feature = "aac")]
+			StreamFormat::Aac => DecoderFormat::Aac,
 		}
 	}
 }
 
-#[derive(derive_more::From)]
-enum StreamKind {
-	/// aka H264 with inline SPS/PPS
-	#[cfg(feature = "h264")]
-	Avc3(super::Avc3),
-	// Boxed because it's a large struct and clippy complains about the size.
-	#[cfg(feature = "mp4")]
-	Fmp4(Box<super::Fmp4>),
-	/// aka H265 with inline SPS/PPS
-	#[cfg(feature = "h265")]
-	Hev1(super::Hev1),
+/// A buffer that can be decoded, implemented for any contiguous [Buf].
+///
+/// This exists so [MediaDecoder] can be used as a trait object.
+pub trait DecoderBuf: Buf + AsRef<[u8]> {}
+
+impl<T: Buf + AsRef<[u8]> + ?Sized> DecoderBuf for T {}
+
+/// A common interface for the concrete decoders, used by [Decoder] and [StreamDecoder].
+///
+/// Implement this to plug in your own format via [Decoder::from_decoder].
+pub trait MediaDecoder: Send {
+	/// Initialize the decoder with out-of-band data, see [Decoder::initialize].
+	fn initialize(&mut self, buf: &mut dyn DecoderBuf) -> anyhow::Result<()>;
+
+	/// Decode a buffer containing an entire frame, see [Decoder::decode_frame].
+	fn decode_frame(&mut self, buf: &mut dyn DecoderBuf, pts: Option<Timestamp>) -> anyhow::Result<()>;
+
+	/// Decode a buffer with unknown frame boundaries, see [StreamDecoder::decode_stream].
+	///
+	/// Formats that require frame boundaries return an error by default.
+	fn decode_stream(&mut self, buf: &mut dyn DecoderBuf, pts: Option<Timestamp>) -> anyhow::Result<()> {
+		let _ = (buf, pts);
+		anyhow::bail!("stream decoding is not supported")
+	}
+
+	/// Check if the decoder has read enough data to be initialized.
+	fn is_initialized(&self) -> bool;
+
+	/// The name of the track created by the decoder, or [None] if not initialized yet or unknown.
+	fn track_name(&self) -> Option<&str> {
+		None
+	}
 }
 
-#[derive(derive_more::From)]
-enum DecoderKind {
-	/// aka H264 with inline SPS/PPS
-	#[cfg(feature = "h264")]
-	Avc3(super::Avc3),
-	// Boxed because it's a large struct and clippy complains about the size.
-	#[cfg(feature = "mp4")]
-	Fmp4(Box<super::Fmp4>),
-	/// aka H265 with inline SPS/PPS
-	#[cfg(feature = "h265")]
-	Hev1(super::Hev1),
-	#[cfg(feature = "aac")]
-	Aac(super::Aac),
-	#[cfg(feature = "opus")]
-	Opus(super::Opus),
+/// Options for the built-in importers, see [Decoder::with_options].
+///
+/// A decoder created via [Decoder::from_decoder] does not support these, so they're ignored with a warning.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ImportOptions {
+	/// Advertise a fixed jitter in the catalog, used by the player to size its jitter buffer.
+	///
+	/// Defaults to [None], which lets the importer compute it (fMP4) or leaves it unset.
+	pub jitter: Option<std::time::Duration>,
+
+	/// Record the keyframe that starts each group, so a player can seek without scanning the track.
+	///
+	/// Defaults to [None]; use [SeekIndex::with_capacity] to bound the memory for live streams.
+	pub seek_index: Option<SeekIndex>,
+
+	/// Return an error instead of buffering a frame, NAL unit, length prefixed packet, or fMP4 atom larger than this.
+	///
+	/// Defaults to [MAX_FRAME_SIZE].
+	pub max_frame_size: usize,
+
+	/// Parse the media without creating tracks, updating the catalog, or writing frames, see [Decoder::validate].
+	///
+	/// A description of the media is collected instead, see [Decoder::media_info].
+	pub dry_run: bool,
+}
+
+impl Default for ImportOptions {
+	fn default() -> Self {
+		Self {
+			jitter: None,
+			seek_index: None,
+			max_frame_size: MAX_FRAME_SIZE,
+			dry_run: false,
+		}
+	}
+}
+
+// The built-in importers, which support [ImportOptions] on top of [MediaDecoder].
+pub(crate) trait Importer: MediaDecoder {
+	fn options(&self) -> &ImportOptions;
+
+	fn set_options(&mut self, options: ImportOptions);
+
+	// The seek index for the track named by [MediaDecoder::track_name], if enabled.
+	fn seek_index(&self) -> Option<&SeekIndex>;
+
+	// The media seen so far in dry run mode, or [None] if not enabled.
+	fn media_info(&self) -> Option<&MediaInfo>;
+
+	// Importers that already start a group at every keyframe, like H.264 or AAC, ignore this by default.
+	fn cut_group(&mut self, timestamp: Timestamp) {
+		let _ = timestamp;
+	}
+}
+
+// The decoder behind [Decoder] and [StreamDecoder].
+enum Inner {
+	Importer(Box<dyn Importer>),
+	Custom(Box<dyn MediaDecoder>),
+}
+
+impl Inner {
+	fn decoder(&self) -> &dyn MediaDecoder {
+		match self {
+			Self::Importer(importer) => importer.as_ref(),
+			Self::Custom(decoder) => decoder.as_ref(),
+		}
+	}
+
+	fn decoder_mut(&mut self) -> &mut dyn MediaDecoder {
+		match self {
+			Self::Importer(importer) => importer.as_mut(),
+			Self::Custom(decoder) => decoder.as_mut(),
+		}
+	}
+
+	// Update the options of a built-in importer, warning instead of silently ignoring them for a custom decoder.
+	fn configure(&mut self, option: &str, update: impl FnOnce(&mut ImportOptions)) {
+		match self {
+			Self::Importer(importer) => {
+				let mut options = importer.options().clone();
+				update(&mut options);
+				importer.set_options(options);
+			}
+			Self::Custom(_) => tracing::warn!(option, "option is not supported by a custom decoder"),
+		}
+	}
+
+	fn cut_group(&mut self, timestamp: Timestamp) {
+		match self {
+			Self::Importer(importer) => importer.cut_group(timestamp),
+			Self::Custom(_) => tracing::warn!(?timestamp, "cut_group is not supported by a custom decoder"),
+		}
+	}
+
+	fn seek_index(&self) -> Option<&SeekIndex> {
+		match self {
+			Self::Importer(importer) => importer.seek_index(),
+			Self::Custom(_) => None,
+		}
+	}
+
+	fn media_info(&self) -> Option<&MediaInfo> {
+		match self {
+			Self::Importer(importer) => importer.media_info(),
+			Self::Custom(_) => None,
+		}
+	}
 }
 
 /// A decoder for formats that support stream decoding (unknown frame boundaries).
 ///
-/// This includes formats like H.264 (AVC3), H.265 (HEV1), and fMP4/CMAF.
+/// This includes formats like H.264 (AVC3), H.265 (HEV1), fMP4/CMAF, and AAC (LOAS).
 /// Use this when the caller does not know the frame boundaries.
 pub struct StreamDecoder {
-	decoder: StreamKind,
+	inner: Inner,
 }
 
 impl StreamDecoder {
 	/// Create a new stream decoder with the given format.
+	// StreamFormat has no variants when only opus is enabled.
+	#[cfg_attr(
+		not(any(feature = "h264", feature = "mp4", feature = "h265", feature = "aac")),
+		allow(unused_variables, unreachable_code)
+	)]
 	pub fn new(broadcast: moq_lite::BroadcastProducer, catalog: hang::CatalogProducer, format: StreamFormat) -> Self {
-		let decoder = match format {
+		let importer: Box<dyn Importer> = match format {
 			#[cfg(feature = "h264")]
-			StreamFormat::Avc3 => super::Avc3::new(broadcast, catalog).into(),
+			StreamFormat::Avc3 => Box::new(super::Avc3::new(broadcast, catalog)),
 			#[cfg(feature = "mp4")]
-			StreamFormat::Fmp4 => Box::new(super::Fmp4::new(broadcast, catalog, super::Fmp4Config::default())).into(),
+			StreamFormat::Fmp4 => Box::new(super::Fmp4::new(broadcast, catalog, super::Fmp4Config::default())),
 			#[cfg(feature = "h265")]
-			StreamFormat::Hev1 => super::Hev1::new(broadcast, catalog).into(),
+			StreamFormat::Hev1 => Box::new(super::Hev1::new(broadcast, catalog)),
+			#[cfg(feature = "aac")]
+			StreamFormat::Aac => Box::new(super::Aac::new(broadcast, catalog)),
 		};
 
-		Self { decoder }
+		Self {
+			inner: Inner::Importer(importer),
+		}
+	}
+
+	/// Create a stream decoder from a custom [MediaDecoder] implementation.
+	///
+	/// [ImportOptions] and [Self::cut_group] are not supported, so they're ignored with a warning.
+	pub fn from_decoder(decoder: Box<dyn MediaDecoder>) -> Self {
+		Self {
+			inner: Inner::Custom(decoder),
+		}
+	}
+
+	/// Replace every option at once, see [ImportOptions].
+	pub fn with_options(mut self, options: ImportOptions) -> Self {
+		self.inner.configure("options", |current| *current = options);
+		self
+	}
+
+	/// Advertise a fixed jitter in the catalog, see [ImportOptions::jitter].
+	pub fn with_jitter(mut self, jitter: Option<std::time::Duration>) -> Self {
+		self.inner.configure("jitter", |options| options.jitter = jitter);
+		self
+	}
+
+	/// Record the keyframe that starts each group, see [ImportOptions::seek_index].
+	pub fn with_seek_index(mut self, index: SeekIndex) -> Self {
+		self.inner
+			.configure("seek_index", |options| options.seek_index = Some(index));
+		self
+	}
+
+	/// Return an error instead of buffering a frame larger than the given size, see [ImportOptions::max_frame_size].
+	pub fn with_max_frame_size(mut self, size: usize) -> Self {
+		self.inner
+			.configure("max_frame_size", |options| options.max_frame_size = size);
+		self
 	}
 
 	/// Initialize the decoder with the given buffer and populate the broadcast.
 	///
-	/// This is not required for self-describing formats like fMP4 or AVC3.
+	/// This is not required for self-describing formats like fMP4, AVC3, or LOAS.
 	///
 	/// The buffer will be fully consumed, or an error will be returned.
 	pub fn initialize<T: Buf + AsRef<[u8]>>(&mut self, buf: &mut T) -> anyhow::Result<()> {
-		match &mut self.decoder {
-			#[cfg(feature = "h264")]
-			StreamKind::Avc3(decoder) => decoder.initialize(buf)?,
-			#[cfg(feature = "mp4")]
-			StreamKind::Fmp4(decoder) => decoder.decode(buf)?,
-			#[cfg(feature = "h265")]
-			StreamKind::Hev1(decoder) => decoder.initialize(buf)?,
-		}
-
+		self.inner.decoder_mut().initialize(buf)?;
 		anyhow::ensure!(!buf.has_remaining(), "buffer was not fully consumed");
 
 		Ok(())
@@ -211,26 +455,40 @@ impl StreamDecoder {
 	///
 	/// If the buffer is not fully consumed, more data is needed.
 	pub fn decode_stream<T: Buf + AsRef<[u8]>>(&mut self, buf: &mut T) -> anyhow::Result<()> {
-		match &mut self.decoder {
-			#[cfg(feature = "h264")]
-			StreamKind::Avc3(decoder) => decoder.decode_stream(buf, None),
-			#[cfg(feature = "mp4")]
-			StreamKind::Fmp4(decoder) => decoder.decode(buf),
-			#[cfg(feature = "h265")]
-			StreamKind::Hev1(decoder) => decoder.decode_stream(buf, None),
-		}
+		self.inner.decoder_mut().decode_stream(buf, None)
+	}
+
+	/// Start a new group on every track at the given timestamp, aligning group boundaries across tracks.
+	///
+	/// The next audio frame at or after the timestamp starts a new group, even in the middle of an fMP4 fragment.
+	/// Video groups can only start with a keyframe, so the next keyframe at or after the timestamp starts one as usual.
+	/// Request a keyframe from the video encoder at the same timestamp for the boundaries to line up.
+	pub fn cut_group(&mut self, timestamp: Timestamp) {
+		self.inner.cut_group(timestamp)
 	}
 
 	/// Check if the decoder has read enough data to be initialized.
 	pub fn is_initialized(&self) -> bool {
-		match &self.decoder {
-			#[cfg(feature = "h264")]
-			StreamKind::Avc3(decoder) => decoder.is_initialized(),
-			#[cfg(feature = "mp4")]
-			StreamKind::Fmp4(decoder) => decoder.is_initialized(),
-			#[cfg(feature = "h265")]
-			StreamKind::Hev1(decoder) => decoder.is_initialized(),
-		}
+		self.inner.decoder().is_initialized()
+	}
+
+	/// The name of the track created by the decoder, or [None] if not initialized yet.
+	///
+	/// fMP4 may contain multiple tracks, in which case this is the one with the lowest track ID.
+	pub fn track_name(&self) -> Option<&str> {
+		self.inner.decoder().track_name()
+	}
+
+	/// The seek index for the track named by [Self::track_name], or [None] if not enabled via [Self::with_seek_index].
+	///
+	/// The index is reset when a new track replaces the previous one.
+	pub fn seek_index(&self) -> Option<&SeekIndex> {
+		self.inner.seek_index()
+	}
+
+	/// The media seen so far, or [None] if [ImportOptions::dry_run] is not enabled.
+	pub fn media_info(&self) -> Option<&MediaInfo> {
+		self.inner.media_info()
 	}
 }
 
@@ -238,26 +496,80 @@ impl StreamDecoder {
 ///
 /// This supports all formats and should be used when the caller knows the frame boundaries.
 pub struct Decoder {
-	decoder: DecoderKind,
+	inner: Inner,
 }
 
 impl Decoder {
 	/// Create a new decoder with the given format.
 	pub fn new(broadcast: moq_lite::BroadcastProducer, catalog: hang::CatalogProducer, format: DecoderFormat) -> Self {
-		let decoder = match format {
+		Self {
+			inner: Inner::Importer(Self::importer(broadcast, catalog, format)),
+		}
+	}
+
+	// DecoderFormat has no variants when no features are enabled.
+	#[cfg_attr(
+		not(any(
+			feature = "h264",
+			feature = "mp4",
+			feature = "h265",
+			feature = "aac",
+			feature = "opus"
+		)),
+		allow(unused_variables, unreachable_code)
+	)]
+	fn importer(
+		broadcast: moq_lite::BroadcastProducer,
+		catalog: hang::CatalogProducer,
+		format: DecoderFormat,
+	) -> Box<dyn Importer> {
+		match format {
 			#[cfg(feature = "h264")]
-			DecoderFormat::Avc3 => super::Avc3::new(broadcast, catalog).into(),
+			DecoderFormat::Avc3 => Box::new(super::Avc3::new(broadcast, catalog)),
 			#[cfg(feature = "mp4")]
-			DecoderFormat::Fmp4 => Box::new(super::Fmp4::new(broadcast, catalog, super::Fmp4Config::default())).into(),
+			DecoderFormat::Fmp4 => Box::new(super::Fmp4::new(broadcast, catalog, super::Fmp4Config::default())),
 			#[cfg(feature = "h265")]
-			DecoderFormat::Hev1 => super::Hev1::new(broadcast, catalog).into(),
+			DecoderFormat::Hev1 => Box::new(super::Hev1::new(broadcast, catalog)),
 			#[cfg(feature = "aac")]
-			DecoderFormat::Aac => super::Aac::new(broadcast, catalog).into(),
+			DecoderFormat::Aac => Box::new(super::Aac::new(broadcast, catalog)),
 			#[cfg(feature = "opus")]
-			DecoderFormat::Opus => super::Opus::new(broadcast, catalog).into(),
-		};
+			DecoderFormat::Opus => Box::new(super::Opus::new(broadcast, catalog)),
+		}
+	}
+
+	/// Create a decoder from a custom [MediaDecoder] implementation.
+	///
+	/// [ImportOptions] and [Self::cut_group] are not supported, so they're ignored with a warning.
+	pub fn from_decoder(decoder: Box<dyn MediaDecoder>) -> Self {
+		Self {
+			inner: Inner::Custom(decoder),
+		}
+	}
+
+	/// Replace every option at once, see [ImportOptions].
+	pub fn with_options(mut self, options: ImportOptions) -> Self {
+		self.inner.configure("options", |current| *current = options);
+		self
+	}
 
-		Self { decoder }
+	/// Advertise a fixed jitter in the catalog, see [ImportOptions::jitter].
+	pub fn with_jitter(mut self, jitter: Option<std::time::Duration>) -> Self {
+		self.inner.configure("jitter", |options| options.jitter = jitter);
+		self
+	}
+
+	/// Record the keyframe that starts each group, see [ImportOptions::seek_index].
+	pub fn with_seek_index(mut self, index: SeekIndex) -> Self {
+		self.inner
+			.configure("seek_index", |options| options.seek_index = Some(index));
+		self
+	}
+
+	/// Return an error instead of buffering a frame larger than the given size, see [ImportOptions::max_frame_size].
+	pub fn with_max_frame_size(mut self, size: usize) -> Self {
+		self.inner
+			.configure("max_frame_size", |options| options.max_frame_size = size);
+		self
 	}
 
 	/// Initialize the decoder with the given buffer and populate the broadcast.
@@ -267,19 +579,7 @@ impl Decoder {
 	///
 	/// The buffer will be fully consumed, or an error will be returned.
 	pub fn initialize<T: Buf + AsRef<[u8]>>(&mut self, buf: &mut T) -> anyhow::Result<()> {
-		match &mut self.decoder {
-			#[cfg(feature = "h264")]
-			DecoderKind::Avc3(decoder) => decoder.initialize(buf)?,
-			#[cfg(feature = "mp4")]
-			DecoderKind::Fmp4(decoder) => decoder.decode(buf)?,
-			#[cfg(feature = "h265")]
-			DecoderKind::Hev1(decoder) => decoder.initialize(buf)?,
-			#[cfg(feature = "aac")]
-			DecoderKind::Aac(decoder) => decoder.initialize(buf)?,
-			#[cfg(feature = "opus")]
-			DecoderKind::Opus(decoder) => decoder.initialize(buf)?,
-		}
-
+		self.inner.decoder_mut().initialize(buf)?;
 		anyhow::ensure!(!buf.has_remaining(), "buffer was not fully consumed");
 
 		Ok(())
@@ -290,46 +590,306 @@ impl Decoder {
 	/// This method should be used when the caller knows the buffer consists of an entire frame.
 	///
 	/// A timestamp may be provided if the format does not contain its own timestamps.
-	/// Otherwise, a value of [None] will use the wall clock time.
+	/// Otherwise, a value of [None] will use the wall clock time, sampled once per call.
+	///
+	/// AVC3 and HEV1 split a buffer containing multiple access units into separate frames.
+	/// Each frame after the first is stepped by the frame duration from the SPS timing info.
+	/// Without it, every frame uses the same timestamp, so prefer one access unit per call.
 	///
 	/// The buffer will be fully consumed, or an error will be returned.
 	/// If the buffer did not contain a frame, future decode calls may fail.
-	pub fn decode_frame<T: Buf + AsRef<[u8]>>(
-		&mut self,
-		buf: &mut T,
-		pts: Option<hang::container::Timestamp>,
-	) -> anyhow::Result<()> {
-		match &mut self.decoder {
-			#[cfg(feature = "h264")]
-			DecoderKind::Avc3(decoder) => decoder.decode_frame(buf, pts)?,
-			#[cfg(feature = "mp4")]
-			DecoderKind::Fmp4(decoder) => decoder.decode(buf)?,
-			#[cfg(feature = "h265")]
-			DecoderKind::Hev1(decoder) => decoder.decode_frame(buf, pts)?,
-			#[cfg(feature = "aac")]
-			DecoderKind::Aac(decoder) => decoder.decode(buf, pts)?,
-			#[cfg(feature = "opus")]
-			DecoderKind::Opus(decoder) => decoder.decode(buf, pts)?,
-		}
-
+	pub fn decode_frame<T: Buf + AsRef<[u8]>>(&mut self, buf: &mut T, pts: Option<Timestamp>) -> anyhow::Result<()> {
+		self.inner.decoder_mut().decode_frame(buf, pts)?;
 		anyhow::ensure!(!buf.has_remaining(), "buffer was not fully consumed");
 
 		Ok(())
 	}
 
+	/// Start a new group on every track at the given timestamp, aligning group boundaries across tracks.
+	///
+	/// The next audio frame at or after the timestamp starts a new group, even in the middle of an fMP4 fragment.
+	/// Video groups can only start with a keyframe, so the next keyframe at or after the timestamp starts one as usual.
+	/// Request a keyframe from the video encoder at the same timestamp for the boundaries to line up.
+	pub fn cut_group(&mut self, timestamp: Timestamp) {
+		self.inner.cut_group(timestamp)
+	}
+
 	/// Check if the decoder has read enough data to be initialized.
 	pub fn is_initialized(&self) -> bool {
-		match &self.decoder {
+		self.inner.decoder().is_initialized()
+	}
+
+	/// The name of the track created by the decoder, or [None] if not initialized yet.
+	///
+	/// fMP4 may contain multiple tracks, in which case this is the one with the lowest track ID.
+	pub fn track_name(&self) -> Option<&str> {
+		self.inner.decoder().track_name()
+	}
+
+	/// The seek index for the track named by [Self::track_name], or [None] if not enabled via [Self::with_seek_index].
+	///
+	/// The index is reset when a new track replaces the previous one.
+	pub fn seek_index(&self) -> Option<&SeekIndex> {
+		self.inner.seek_index()
+	}
+
+	/// The media seen so far, or [None] if [ImportOptions::dry_run] is not enabled.
+	pub fn media_info(&self) -> Option<&MediaInfo> {
+		self.inner.media_info()
+	}
+
+	/// Parse the buffer without publishing anything, returning a description of the media.
+	///
+	/// The decoder runs in dry run mode, so no tracks are created, the catalog is untouched, and frames are only counted.
+	/// Formats that require initialization (AAC, Opus) parse the buffer via [Self::initialize], so no frames are seen.
+	/// Otherwise, the buffer is parsed via [Self::decode_frame].
+	/// The same errors are returned as the real decode path.
+	#[cfg_attr(
+		not(any(
+			feature = "h264",
+			feature = "mp4",
+			feature = "h265",
+			feature = "aac",
+			feature = "opus"
+		)),
+		allow(unused_variables, unused_mut, unreachable_code)
+	)]
+	pub fn validate<T: Buf + AsRef<[u8]>>(format: DecoderFormat, buf: &mut T) -> anyhow::Result<MediaInfo> {
+		// Never written in dry run mode.
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+
+		let options = ImportOptions {
+			dry_run: true,
+			..Default::default()
+		};
+		let mut decoder = Self::new(broadcast, catalog, format).with_options(options);
+
+		match format {
 			#[cfg(feature = "h264")]
-			DecoderKind::Avc3(decoder) => decoder.is_initialized(),
+			DecoderFormat::Avc3 => decoder.decode_frame(buf, None)?,
 			#[cfg(feature = "mp4")]
-			DecoderKind::Fmp4(decoder) => decoder.is_initialized(),
+			DecoderFormat::Fmp4 => decoder.decode_frame(buf, None)?,
 			#[cfg(feature = "h265")]
-			DecoderKind::Hev1(decoder) => decoder.is_initialized(),
+			DecoderFormat::Hev1 => decoder.decode_frame(buf, None)?,
 			#[cfg(feature = "aac")]
-			DecoderKind::Aac(decoder) => decoder.is_initialized(),
+			DecoderFormat::Aac => decoder.initialize(buf)?,
 			#[cfg(feature = "opus")]
-			DecoderKind::Opus(decoder) => decoder.is_initialized(),
+			DecoderFormat::Opus => decoder.initialize(buf)?,
 		}
+
+		let info = decoder.media_info().context("dry run is not supported")?;
+		Ok(info.clone())
+	}
+}
+
+/// A description of the media found by [Decoder::validate], or by a decoder in dry run mode.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct MediaInfo {
+	/// The detected video tracks, including the codec and resolution.
+	pub video: Vec<hang::catalog::VideoConfig>,
+
+	/// The detected audio tracks, including the codec, sample rate, and channel count.
+	pub audio: Vec<hang::catalog::AudioConfig>,
+
+	/// The number of frames seen across all tracks.
+	pub frames: u64,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn supported_formats() {
+		let formats = [
+			("avc3", cfg!(feature = "h264")),
+			("fmp4", cfg!(feature = "mp4")),
+			("hev1", cfg!(feature = "h265")),
+			("aac", cfg!(feature = "aac")),
+			("opus", cfg!(feature = "opus")),
+		];
+
+		let expected: Vec<_> = formats
+			.iter()
+			.filter(|(_, enabled)| *enabled)
+			.map(|(name, _)| *name)
+			.collect();
+		let supported: Vec<_> = DecoderFormat::supported()
+			.iter()
+			.map(|format| format.to_string())
+			.collect();
+		assert_eq!(supported, expected);
+
+		for (name, enabled) in formats {
+			match name.parse::<DecoderFormat>() {
+				Ok(format) => assert!(enabled && format.to_string() == name),
+				Err(err) => assert!(!enabled && matches!(err, Error::FormatNotEnabled(_)), "{name}: {err}"),
+			}
+		}
+
+		let expected: Vec<_> = expected.into_iter().filter(|name| *name != "opus").collect();
+		let supported: Vec<_> = StreamFormat::supported()
+			.iter()
+			.map(|format| format.to_string())
+			.collect();
+		assert_eq!(supported, expected);
+
+		assert!(matches!("mp3".parse::<DecoderFormat>(), Err(Error::UnknownFormat(_))));
+		assert!(matches!("mp3".parse::<StreamFormat>(), Err(Error::UnknownFormat(_))));
+	}
+
+	// A custom decoder that records each frame.
+	#[derive(Default)]
+	struct Recorder {
+		frames: std::sync::Arc<std::sync::Mutex<Vec<Vec<u8>>>>,
+	}
+
+	impl MediaDecoder for Recorder {
+		fn initialize(&mut self, buf: &mut dyn DecoderBuf) -> anyhow::Result<()> {
+			buf.advance(buf.remaining());
+			Ok(())
+		}
+
+		fn decode_frame(&mut self, buf: &mut dyn DecoderBuf, _pts: Option<Timestamp>) -> anyhow::Result<()> {
+			self.frames.lock().unwrap().push(buf.as_ref().to_vec());
+			buf.advance(buf.remaining());
+			Ok(())
+		}
+
+		fn is_initialized(&self) -> bool {
+			true
+		}
+
+		fn track_name(&self) -> Option<&str> {
+			Some("custom")
+		}
+	}
+
+	#[test]
+	fn custom_decoder() {
+		let recorder = Recorder::default();
+		let frames = recorder.frames.clone();
+
+		let mut decoder = Decoder::from_decoder(Box::new(recorder));
+		decoder.decode_frame(&mut &b"hello"[..], None).unwrap();
+		assert_eq!(decoder.track_name(), Some("custom"));
+		assert_eq!(*frames.lock().unwrap(), [b"hello".to_vec()]);
+
+		// Stream decoding is unsupported by default.
+		let mut decoder = StreamDecoder::from_decoder(Box::new(Recorder::default()));
+		assert!(decoder.decode_stream(&mut &b"hello"[..]).is_err());
+	}
+
+	#[test]
+	fn custom_decoder_ignores_options() {
+		let recorder = Recorder::default();
+		let frames = recorder.frames.clone();
+
+		// The options are ignored with a warning, since a custom decoder can't enforce them.
+		let mut decoder = Decoder::from_decoder(Box::new(recorder)).with_max_frame_size(1);
+		decoder.decode_frame(&mut &b"hello"[..], None).unwrap();
+		assert!(decoder.seek_index().is_none());
+		assert_eq!(decoder.media_info(), None);
+		assert_eq!(frames.lock().unwrap().len(), 1);
+	}
+
+	#[cfg(feature = "aac")]
+	#[test]
+	fn options_reach_importer() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+
+		let options = ImportOptions {
+			max_frame_size: 4,
+			..Default::default()
+		};
+		let mut decoder = Decoder::new(broadcast, catalog, DecoderFormat::Aac).with_options(options);
+
+		// AAC-LC, 44.1kHz, stereo
+		decoder.initialize(&mut &[0x12, 0x10][..]).unwrap();
+		decoder.decode_frame(&mut &[0; 4][..], None).unwrap();
+
+		let err = decoder.decode_frame(&mut &[0; 5][..], None).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(Error::InvalidFrame)));
+	}
+
+	#[cfg(feature = "aac")]
+	#[test]
+	fn jitter_reaches_catalog() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+
+		let mut decoder = Decoder::new(broadcast, catalog.clone(), DecoderFormat::Aac)
+			.with_jitter(Some(std::time::Duration::from_millis(100)));
+
+		// AAC-LC, 44.1kHz, stereo
+		decoder.initialize(&mut &[0x12, 0x10][..]).unwrap();
+
+		let json = catalog.lock().to_string().unwrap();
+		let parsed = hang::Catalog::from_str(&json).unwrap();
+		let config = parsed.audio.renditions.values().next().unwrap();
+		assert_eq!(config.jitter, Some(moq_lite::Time::from_millis(100).unwrap()));
+	}
+
+	#[cfg(feature = "aac")]
+	#[test]
+	fn jitter_defaults_to_none() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+
+		let mut decoder = Decoder::new(broadcast, catalog.clone(), DecoderFormat::Aac);
+		decoder.initialize(&mut &[0x12, 0x10][..]).unwrap();
+
+		let config = catalog.lock().audio.renditions.values().next().unwrap().clone();
+		assert_eq!(config.jitter, None);
+	}
+
+	#[cfg(feature = "aac")]
+	#[test]
+	fn track_name_after_initialize() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+
+		let mut decoder = Decoder::new(broadcast, catalog.clone(), DecoderFormat::Aac);
+		assert_eq!(decoder.track_name(), None);
+
+		decoder.initialize(&mut &[0x12, 0x10][..]).unwrap();
+
+		let name = decoder.track_name().unwrap();
+		assert!(catalog.lock().audio.renditions.contains_key(name));
+	}
+
+	#[cfg(feature = "h264")]
+	#[test]
+	fn validate_avc3() {
+		let sps = [0, 0, 0, 1, 0x67, 0x42, 0xc0, 0x1e, 0xda, 0x05, 0x07, 0xe4];
+		let pps = [0, 0, 0, 1, 0x68, 0xce, 0x3c, 0x80];
+		let aud = [0, 0, 0, 1, 0x09, 0xf0];
+		let idr = [0, 0, 0, 1, 0x65, 0x88, 0x84, 0x00];
+
+		let mut buf = bytes::Bytes::from([&sps[..], &pps, &aud, &idr, &aud, &idr].concat());
+		let info = Decoder::validate(DecoderFormat::Avc3, &mut buf).unwrap();
+
+		assert_eq!(info.video.len(), 1);
+		assert!(info.audio.is_empty());
+		assert!(info.video[0].coded_width.is_some());
+		assert_eq!(info.frames, 2);
+	}
+
+	#[cfg(feature = "aac")]
+	#[test]
+	fn validate_aac() {
+		// AAC-LC, 44.1kHz, stereo
+		let info = Decoder::validate(DecoderFormat::Aac, &mut &[0x12, 0x10][..]).unwrap();
+		assert_eq!(info.audio.len(), 1);
+		assert_eq!(info.audio[0].sample_rate, 44100);
+		assert_eq!(info.audio[0].channel_count, 2);
+		assert_eq!(info.frames, 0);
+
+		// AAC-LC, explicit 0Hz, stereo
+		let err = Decoder::validate(DecoderFormat::Aac, &mut &[0x17, 0x80, 0x00, 0x00, 0x10][..]).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(Error::InvalidCodec)));
 	}
 }
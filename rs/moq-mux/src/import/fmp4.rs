@@ -1,3 +1,6 @@
+use super::output::{Output, TrackConfig, TrackKind};
+use super::{DecoderBuf, ImportOptions, Importer, MediaDecoder, MediaInfo, SeekIndex, ensure_frame_size};
+
 use anyhow::Context;
 use bytes::{Buf, Bytes, BytesMut};
 use hang::catalog::{AAC, AV1, AudioCodec, AudioConfig, Container, H264, H265, VP9, VideoCodec, VideoConfig};
@@ -33,11 +36,8 @@ pub struct Fmp4Config {
 /// - AAC (MP4A)
 /// - Opus
 pub struct Fmp4 {
-	/// The broadcast being produced
-	broadcast: moq_lite::BroadcastProducer,
-
-	/// The catalog being produced
-	catalog: hang::CatalogProducer,
+	// The broadcast and catalog being produced, and the options.
+	output: Output,
 
 	// A lookup to tracks in the broadcast
 	tracks: HashMap<u32, Fmp4Track>,
@@ -56,16 +56,11 @@ pub struct Fmp4 {
 	moof_raw: Option<Bytes>,
 }
 
-#[derive(PartialEq, Debug)]
-enum TrackKind {
-	Video,
-	Audio,
-}
-
 struct Fmp4Track {
 	kind: TrackKind,
 
-	producer: moq_lite::TrackProducer,
+	// The track being produced, or [None] in dry run mode.
+	producer: Option<moq_lite::TrackProducer>,
 
 	// The current group being written, only used for passthrough mode.
 	group: Option<moq_lite::GroupProducer>,
@@ -78,10 +73,16 @@ struct Fmp4Track {
 
 	// The minimum duration between frames for this track.
 	min_duration: Option<Timestamp>,
+
+	// Records the keyframe that starts each group, if enabled.
+	seek_index: Option<SeekIndex>,
+
+	// Start a new group at the first frame at or after this timestamp, see [Fmp4::cut_group].
+	cut: Option<Timestamp>,
 }
 
 impl Fmp4Track {
-	fn new(kind: TrackKind, producer: moq_lite::TrackProducer) -> Self {
+	fn new(kind: TrackKind, producer: Option<moq_lite::TrackProducer>, seek_index: Option<SeekIndex>) -> Self {
 		Self {
 			kind,
 			producer,
@@ -89,8 +90,35 @@ impl Fmp4Track {
 			jitter: None,
 			last_timestamp: None,
 			min_duration: None,
+			seek_index,
+			cut: None,
 		}
 	}
+
+	fn name(&self) -> Option<&str> {
+		self.producer.as_ref().map(|producer| producer.info.name.as_str())
+	}
+
+	// Whether the frame at the given timestamp should start a new group because of a pending cut.
+	fn is_cut(&self, timestamp: Timestamp) -> bool {
+		self.cut.is_some_and(|cut| timestamp >= cut)
+	}
+
+	// Start a new group with the given keyframe, recording it in the seek index.
+	fn append_group(&mut self, timestamp: Timestamp) -> anyhow::Result<moq_lite::GroupProducer> {
+		let group = self.producer.as_mut().context("missing track")?.append_group();
+
+		// Any new group at or after the cut satisfies it.
+		if self.is_cut(timestamp) {
+			self.cut = None;
+		}
+
+		if let Some(index) = &mut self.seek_index {
+			index.push(timestamp, group.info.sequence);
+		}
+
+		Ok(group)
+	}
 }
 
 impl Fmp4 {
@@ -99,12 +127,11 @@ impl Fmp4 {
 	/// The broadcast will be populated with tracks as they're discovered in the fMP4 file.
 	pub fn new(broadcast: moq_lite::BroadcastProducer, catalog: hang::CatalogProducer, config: Fmp4Config) -> Self {
 		Self {
-			catalog,
+			output: Output::new(broadcast, catalog),
 			tracks: HashMap::default(),
 			moov: None,
 			moof: None,
 			moof_size: 0,
-			broadcast,
 			config,
 			moof_raw: None,
 		}
@@ -120,8 +147,21 @@ impl Fmp4 {
 		Ok(())
 	}
 
-	/// Decode a buffer of bytes.
+	/// Decode each complete atom in the buffer, which may split atoms at arbitrary boundaries.
+	///
+	/// If the buffer ends with a partial atom, it is left unconsumed and more data is needed.
+	/// The caller should append to the same buffer and call this again, as [Self::decode_from] does.
 	pub fn decode<T: Buf + AsRef<[u8]>>(&mut self, buf: &mut T) -> anyhow::Result<()> {
+		self.decode_atoms(buf)?;
+
+		// Don't let the caller buffer an atom that will exceed the limit.
+		ensure_frame_size(partial_atom_size(buf.as_ref()), self.output.max_frame_size())?;
+
+		Ok(())
+	}
+
+	// Decode each complete atom, leaving any partial atom in the buffer.
+	fn decode_atoms<T: Buf + AsRef<[u8]>>(&mut self, buf: &mut T) -> anyhow::Result<()> {
 		let mut cursor = std::io::Cursor::new(buf);
 		let mut position = 0;
 
@@ -168,33 +208,102 @@ impl Fmp4 {
 		self.moov.is_some()
 	}
 
+	/// The names of the tracks in the broadcast, ordered by their fMP4 track ID.
+	///
+	/// This is empty until the moov atom has been decoded, and always empty in dry run mode.
+	pub fn track_names(&self) -> impl Iterator<Item = &str> {
+		let mut tracks: Vec<_> = self.tracks.iter().collect();
+		tracks.sort_by_key(|(id, _)| **id);
+		tracks.into_iter().filter_map(|(_, track)| track.name())
+	}
+
+	/// Advertise a fixed jitter in the catalog instead of computing it from each fragment.
+	///
+	/// The player uses this to size its jitter buffer, so a larger value trades latency for smoothness.
+	/// This only applies to tracks created after this call.
+	pub fn set_jitter(&mut self, jitter: Option<std::time::Duration>) {
+		self.output.set_jitter(jitter);
+	}
+
+	/// The options used by this importer.
+	pub fn options(&self) -> &ImportOptions {
+		self.output.options()
+	}
+
+	/// Replace every option at once, see [ImportOptions].
+	///
+	/// The seek index is copied to each track, see [Self::seek_index].
+	/// The maximum frame size applies to each atom as soon as its header is decoded, so it also limits the size of a fragment.
+	/// This only applies to tracks created after this call, so call it before decoding.
+	pub fn set_options(&mut self, options: ImportOptions) {
+		self.output.set_options(options);
+	}
+
+	/// Start a new group on every track at the given timestamp, see [super::Decoder::cut_group].
+	///
+	/// Audio fragments are split at the first sample at or after the timestamp.
+	/// This only applies to tracks that have already been created.
+	pub fn cut_group(&mut self, timestamp: Timestamp) {
+		for track in self.tracks.values_mut() {
+			track.cut = Some(timestamp);
+		}
+	}
+
+	/// The media seen so far in dry run mode, or [None] if not enabled.
+	pub fn media_info(&self) -> Option<&MediaInfo> {
+		self.output.media_info()
+	}
+
+	/// The seek index for the given track, or [None] if not enabled.
+	pub fn seek_index(&self, track: &str) -> Option<&SeekIndex> {
+		self.tracks
+			.values()
+			.find(|t| t.name() == Some(track))?
+			.seek_index
+			.as_ref()
+	}
+
 	fn init(&mut self, moov: Moov) -> anyhow::Result<()> {
-		// Clone the catalog to avoid the borrow checker.
-		let mut catalog = self.catalog.clone();
-		let mut catalog = catalog.lock();
+		let jitter = self.output.jitter()?;
+
+		// Parse every track before locking the catalog, so it's only held for the update.
+		let mut configs = Vec::with_capacity(moov.trak.len());
 
 		for trak in &moov.trak {
 			let track_id = trak.tkhd.track_id;
 			let handler = &trak.mdia.hdlr.handler;
 
-			let (kind, track) = match handler.as_ref() {
-				b"vide" => {
-					let config = self.init_video(trak)?;
-					let track = catalog.video.create_track("m4s", config.clone());
-					(TrackKind::Video, track)
-				}
-				b"soun" => {
-					let config = self.init_audio(trak)?;
-					let track = catalog.audio.create_track("m4s", config.clone());
-					(TrackKind::Audio, track)
-				}
+			let config = match handler.as_ref() {
+				b"vide" => TrackConfig::Video(VideoConfig {
+					jitter,
+					..self.init_video(trak)?
+				}),
+				b"soun" => TrackConfig::Audio(AudioConfig {
+					jitter,
+					..self.init_audio(trak)?
+				}),
 				b"sbtl" => anyhow::bail!("subtitle tracks are not supported"),
 				handler => anyhow::bail!("unknown track type: {:?}", handler),
 			};
 
-			let track = self.broadcast.create_track(track);
+			configs.push((track_id, config));
+		}
 
-			self.tracks.insert(track_id, Fmp4Track::new(kind, track));
+		// Add every track in a single catalog update, replacing any from a previous moov.
+		let ids: Vec<_> = configs
+			.iter()
+			.map(|(track_id, config)| (*track_id, config.kind()))
+			.collect();
+		let configs = configs.into_iter().map(|(_, config)| config).collect();
+
+		// There are no producers in dry run mode, but each sample is still validated.
+		let mut producers = self.output.replace("m4s", configs).into_iter();
+		let seek_index = self.output.options().seek_index.clone();
+
+		self.tracks.clear();
+		for (track_id, kind) in ids {
+			let track = Fmp4Track::new(kind, producers.next(), seek_index.clone());
+			self.tracks.insert(track_id, track);
 		}
 
 		self.moov = Some(moov);
@@ -405,6 +514,7 @@ impl Fmp4 {
 					description: None, // TODO?
 					container,
 					jitter: None,
+					gain_db: None,
 				}
 			}
 			mp4_atom::Codec::Opus(opus) => {
@@ -416,6 +526,8 @@ impl Fmp4 {
 					description: None, // TODO?
 					container,
 					jitter: None,
+					// The output gain is in Q7.8 dB.
+					gain_db: (opus.dops.output_gain != 0).then(|| opus.dops.output_gain as f32 / 256.0),
 				}
 			}
 			mp4_atom::Codec::Unknown(unknown) => anyhow::bail!("unknown codec: {:?}", unknown),
@@ -467,7 +579,7 @@ impl Fmp4 {
 			// Ideally these should both be the same value (a single frame lul).
 			let mut min_timestamp = None;
 			let mut max_timestamp = None;
-			let mut contains_keyframe = false;
+			let mut first_keyframe = None;
 
 			for trun in &traf.trun {
 				let tfhd = &traf.tfhd;
@@ -524,9 +636,12 @@ impl Fmp4 {
 						}
 					};
 
-					contains_keyframe |= keyframe;
+					if keyframe && first_keyframe.is_none() {
+						first_keyframe = Some(timestamp);
+					}
 
-					if !self.config.passthrough {
+					// Only count the frame in dry run mode.
+					if !self.output.count_frame() && !self.config.passthrough {
 						// TODO Avoid a copy if mp4-atom switches to using Bytes?
 						let payload = Bytes::copy_from_slice(&mdat.data[offset..(offset + size)]);
 
@@ -545,7 +660,7 @@ impl Fmp4 {
 									// Close the previous group if it exists.
 									group.close();
 								}
-								track.producer.append_group()
+								track.append_group(timestamp)?
 							}
 							// If this is a video non-keyframe, we use the previous group.
 							TrackKind::Video => track.group.take().context("no keyframe at start")?,
@@ -554,7 +669,15 @@ impl Fmp4 {
 								// This is an optimization to avoid a burst of tiny groups, possibly hitting MAX_STREAMS, when it doesn't really matter.
 								// ex. 2s of audio: 1 group instead of 90 groups.
 								// Technically, individual groups are better for skipping, but it's a moot point if fMP4 is introducing so much latency.
-								track.group.take().unwrap_or_else(|| track.producer.append_group())
+								// The exception is an explicit cut, which splits the fragment to align with the other tracks.
+								match track.group.take() {
+									Some(group) if !track.is_cut(timestamp) => group,
+									Some(group) => {
+										group.close();
+										track.append_group(timestamp)?
+									}
+									None => track.append_group(timestamp)?,
+								}
 							}
 						};
 
@@ -585,13 +708,13 @@ impl Fmp4 {
 			}
 
 			// If we're doing passthrough mode, then we write one giant fragment instead of individual frames.
-			if self.config.passthrough {
-				let mut group = if contains_keyframe {
+			if self.config.passthrough && !self.output.is_dry_run() {
+				let mut group = if let Some(timestamp) = first_keyframe {
 					if let Some(group) = track.group.take() {
 						group.close();
 					}
 
-					track.producer.append_group()
+					track.append_group(timestamp)?
 				} else {
 					track.group.take().context("no keyframe at start")?
 				};
@@ -615,7 +738,11 @@ impl Fmp4 {
 				}
 			}
 
-			if let (Some(min), Some(max), Some(min_duration)) = (min_timestamp, max_timestamp, track.min_duration) {
+			// Compute the jitter unless a fixed value was configured.
+			if self.output.options().jitter.is_none()
+				&& !self.output.is_dry_run()
+				&& let (Some(min), Some(max), Some(min_duration)) = (min_timestamp, max_timestamp, track.min_duration)
+			{
 				// We report the minimum buffer required as the difference between the min and max frames.
 				// We also add the duration between frames to account for the frame rate.
 				// ex. for 2s fragments, this should be exactly 2s if we did everything correctly.
@@ -625,23 +752,16 @@ impl Fmp4 {
 					track.jitter = Some(jitter);
 
 					// Update the catalog with the new jitter
-					let mut catalog = self.catalog.lock();
+					let name = track.name().context("missing track")?;
+					let mut catalog = self.output.catalog().lock();
 
 					match track.kind {
 						TrackKind::Video => {
-							let config = catalog
-								.video
-								.renditions
-								.get_mut(&track.producer.info.name)
-								.context("missing video config")?;
+							let config = catalog.video.renditions.get_mut(name).context("missing video config")?;
 							config.jitter = Some(jitter.convert()?);
 						}
 						TrackKind::Audio => {
-							let config = catalog
-								.audio
-								.renditions
-								.get_mut(&track.producer.info.name)
-								.context("missing audio config")?;
+							let config = catalog.audio.renditions.get_mut(name).context("missing audio config")?;
 							config.jitter = Some(jitter.convert()?);
 						}
 					}
@@ -653,15 +773,398 @@ impl Fmp4 {
 	}
 }
 
-impl Drop for Fmp4 {
-	fn drop(&mut self) {
-		let mut catalog = self.catalog.lock();
+impl MediaDecoder for Fmp4 {
+	fn initialize(&mut self, mut buf: &mut dyn DecoderBuf) -> anyhow::Result<()> {
+		self.decode(&mut buf)
+	}
 
-		for track in self.tracks.values() {
-			match track.kind {
-				TrackKind::Video => catalog.video.remove_track(&track.producer.info).is_some(),
-				TrackKind::Audio => catalog.audio.remove_track(&track.producer.info).is_some(),
-			};
+	// fMP4 contains its own timestamps, so the hint is ignored.
+	fn decode_frame(&mut self, mut buf: &mut dyn DecoderBuf, _pts: Option<Timestamp>) -> anyhow::Result<()> {
+		self.decode(&mut buf)
+	}
+
+	fn decode_stream(&mut self, mut buf: &mut dyn DecoderBuf, _pts: Option<Timestamp>) -> anyhow::Result<()> {
+		self.decode(&mut buf)
+	}
+
+	fn is_initialized(&self) -> bool {
+		Fmp4::is_initialized(self)
+	}
+
+	fn track_name(&self) -> Option<&str> {
+		self.track_names().next()
+	}
+}
+
+impl Importer for Fmp4 {
+	fn options(&self) -> &ImportOptions {
+		Fmp4::options(self)
+	}
+
+	fn set_options(&mut self, options: ImportOptions) {
+		Fmp4::set_options(self, options)
+	}
+
+	fn media_info(&self) -> Option<&MediaInfo> {
+		Fmp4::media_info(self)
+	}
+
+	fn cut_group(&mut self, timestamp: Timestamp) {
+		Fmp4::cut_group(self, timestamp)
+	}
+
+	fn seek_index(&self) -> Option<&SeekIndex> {
+		Fmp4::seek_index(self, self.track_names().next()?)
+	}
+}
+
+// The size of a partial atom once complete, based on its header, or the buffered size if larger or unknown.
+fn partial_atom_size(buf: &[u8]) -> usize {
+	let declared = match buf.get(..4).map(|size| u32::from_be_bytes(size.try_into().unwrap())) {
+		// A 64-bit size follows the type.
+		Some(1) => buf.get(8..16).map(|size| u64::from_be_bytes(size.try_into().unwrap())),
+		Some(size) => Some(size as u64),
+		None => None,
+	};
+
+	let declared = declared.map_or(0, |size| usize::try_from(size).unwrap_or(usize::MAX));
+	declared.max(buf.len())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::FutureExt;
+	use mp4_atom::Encode;
+
+	// An Opus track with the given ID, using a 48kHz timescale.
+	fn opus_trak(track_id: u32) -> Trak {
+		let opus = mp4_atom::Opus {
+			audio: mp4_atom::Audio {
+				data_reference_index: 1,
+				channel_count: 2,
+				sample_size: 16,
+				sample_rate: 48_000.into(),
+			},
+			dops: mp4_atom::Dops {
+				output_channel_count: 2,
+				pre_skip: 0,
+				input_sample_rate: 48_000,
+				output_gain: 0,
+			},
+			btrt: None,
+		};
+
+		Trak {
+			tkhd: mp4_atom::Tkhd {
+				track_id,
+				..Default::default()
+			},
+			mdia: mp4_atom::Mdia {
+				mdhd: mp4_atom::Mdhd {
+					timescale: 48_000,
+					..Default::default()
+				},
+				hdlr: mp4_atom::Hdlr {
+					handler: b"soun".into(),
+					..Default::default()
+				},
+				minf: mp4_atom::Minf {
+					smhd: Some(Default::default()),
+					stbl: mp4_atom::Stbl {
+						stsd: mp4_atom::Stsd {
+							codecs: vec![opus.into()],
+						},
+						..Default::default()
+					},
+					..Default::default()
+				},
+			},
+			..Default::default()
+		}
+	}
+
+	// A VP8 track with the given ID, using a millisecond timescale.
+	fn vp8_trak(track_id: u32) -> Trak {
+		let vp08 = mp4_atom::Vp08 {
+			visual: mp4_atom::Visual {
+				data_reference_index: 1,
+				width: 320,
+				height: 240,
+				..Default::default()
+			},
+			..Default::default()
+		};
+
+		Trak {
+			tkhd: mp4_atom::Tkhd {
+				track_id,
+				..Default::default()
+			},
+			mdia: mp4_atom::Mdia {
+				mdhd: mp4_atom::Mdhd {
+					timescale: 1_000,
+					..Default::default()
+				},
+				hdlr: mp4_atom::Hdlr {
+					handler: b"vide".into(),
+					..Default::default()
+				},
+				minf: mp4_atom::Minf {
+					vmhd: Some(Default::default()),
+					stbl: mp4_atom::Stbl {
+						stsd: mp4_atom::Stsd {
+							codecs: vec![vp08.into()],
+						},
+						..Default::default()
+					},
+					..Default::default()
+				},
+			},
+			..Default::default()
 		}
 	}
+
+	fn encode_moov(trak: Vec<Trak>) -> Vec<u8> {
+		let moov = Moov {
+			trak,
+			..Default::default()
+		};
+
+		let mut buf = Vec::new();
+		moov.encode(&mut buf).unwrap();
+		buf
+	}
+
+	// An init segment with a single Opus track.
+	fn init_segment() -> Vec<u8> {
+		encode_moov(vec![opus_trak(1)])
+	}
+
+	// A fragment for a single track, where each sample is flagged as a keyframe or not.
+	fn track_fragment(track_id: u32, base_media_decode_time: u64, duration: u32, samples: &[(&[u8], bool)]) -> Vec<u8> {
+		let moof = Moof {
+			traf: vec![mp4_atom::Traf {
+				tfhd: mp4_atom::Tfhd {
+					track_id,
+					..Default::default()
+				},
+				tfdt: Some(mp4_atom::Tfdt { base_media_decode_time }),
+				trun: vec![mp4_atom::Trun {
+					data_offset: None,
+					entries: samples
+						.iter()
+						.map(|(sample, keyframe)| mp4_atom::TrunEntry {
+							duration: Some(duration),
+							size: Some(sample.len() as u32),
+							// kSampleDependsOnNoOther or kSampleIsNonSyncSample
+							flags: Some(if *keyframe { 0x0200_0000 } else { 0x0001_0000 }),
+							..Default::default()
+						})
+						.collect(),
+				}],
+				..Default::default()
+			}],
+			..Default::default()
+		};
+
+		let mdat = Mdat {
+			data: samples.iter().flat_map(|(sample, _)| sample.iter().copied()).collect(),
+		};
+
+		let mut buf = Vec::new();
+		moof.encode(&mut buf).unwrap();
+		mdat.encode(&mut buf).unwrap();
+		buf
+	}
+
+	// A fragment with two 20ms samples.
+	fn fragment() -> Vec<u8> {
+		track_fragment(1, 0, 960, &[(&[0x01, 0x02, 0x03], true), (&[0x04, 0x05], true)])
+	}
+
+	// Decode the init segment and fragment in chunks of the given size, returning the raw frames.
+	fn decode_chunked(chunk: usize) -> Vec<Bytes> {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut fmp4 = Fmp4::new(broadcast, catalog, Fmp4Config::default());
+
+		// Append each chunk to the unconsumed tail, like decode_from.
+		let mut buffer = BytesMut::new();
+		for data in init_segment().chunks(chunk) {
+			buffer.extend_from_slice(data);
+			fmp4.decode(&mut buffer).unwrap();
+		}
+		assert!(fmp4.is_initialized());
+		assert!(buffer.is_empty());
+
+		let mut track = fmp4.tracks[&1].producer.as_ref().unwrap().consume();
+
+		for data in fragment().chunks(chunk) {
+			buffer.extend_from_slice(data);
+			fmp4.decode(&mut buffer).unwrap();
+		}
+		assert!(buffer.is_empty());
+
+		let group = track.next_group().now_or_never().unwrap().unwrap().unwrap();
+		(0..)
+			.map_while(|index| group.get_frame(index).now_or_never()?.ok()?)
+			.map(|mut frame| frame.read_all().now_or_never().unwrap().unwrap())
+			.collect()
+	}
+
+	#[test]
+	fn decode_split_atoms() {
+		let whole = decode_chunked(usize::MAX);
+		assert_eq!(whole.len(), 2);
+
+		assert_eq!(decode_chunked(1), whole);
+		assert_eq!(decode_chunked(7), whole);
+	}
+
+	#[test]
+	fn decode_leaves_partial_atom() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut fmp4 = Fmp4::new(broadcast, catalog, Fmp4Config::default());
+
+		// The partial atom is left for the caller, so a truncated buffer isn't silently dropped.
+		let init = init_segment();
+		let mut buf = &init[..init.len() - 1];
+		fmp4.decode(&mut buf).unwrap();
+		assert_eq!(buf.len(), init.len() - 1);
+		assert!(!fmp4.is_initialized());
+	}
+
+	#[test]
+	fn seek_index_records_fragments() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut fmp4 = Fmp4::new(broadcast, catalog, Fmp4Config::default());
+		fmp4.set_options(ImportOptions {
+			seek_index: Some(SeekIndex::new()),
+			..Default::default()
+		});
+
+		fmp4.decode(&mut init_segment().as_slice()).unwrap();
+		fmp4.decode(&mut fragment().as_slice()).unwrap();
+
+		// Audio is grouped per fragment, starting with the first sample.
+		let name = fmp4.track_names().next().unwrap().to_string();
+		let points: Vec<_> = fmp4.seek_index(&name).unwrap().points().copied().collect();
+		assert_eq!(points.len(), 1);
+		assert_eq!(points[0].timestamp, Timestamp::ZERO);
+		assert_eq!(points[0].group, 0);
+	}
+
+	#[test]
+	fn max_frame_size() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut fmp4 = Fmp4::new(broadcast, catalog, Fmp4Config::default());
+		fmp4.set_options(ImportOptions {
+			max_frame_size: 1024,
+			..Default::default()
+		});
+
+		// Atoms under the limit are decoded as usual.
+		fmp4.decode(&mut init_segment().as_slice()).unwrap();
+		fmp4.decode(&mut fragment().as_slice()).unwrap();
+
+		// An mdat declaring 4GB is rejected before it's buffered.
+		let mut buf: &[u8] = &[0xff, 0xff, 0xff, 0xff, b'm', b'd', b'a', b't'];
+		let err = fmp4.decode(&mut buf).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(hang::Error::InvalidFrame)));
+	}
+
+	#[test]
+	fn cut_group_aligns_tracks() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut fmp4 = Fmp4::new(broadcast, catalog, Fmp4Config::default());
+		fmp4.set_options(ImportOptions {
+			seek_index: Some(SeekIndex::new()),
+			..Default::default()
+		});
+
+		fmp4.decode(&mut encode_moov(vec![opus_trak(1), vp8_trak(2)]).as_slice())
+			.unwrap();
+
+		let ms = |ms: u64| Timestamp::from_micros(ms * 1_000).unwrap();
+		fmp4.cut_group(ms(40));
+
+		// 80ms fragments, with a video keyframe at the cut.
+		let audio: [(&[u8], bool); 4] = [(&[0x01], true), (&[0x02], true), (&[0x03], true), (&[0x04], true)];
+		let video: [(&[u8], bool); 4] = [(&[0x05], true), (&[0x06], false), (&[0x07], true), (&[0x08], false)];
+		fmp4.decode(&mut track_fragment(1, 0, 960, &audio).as_slice()).unwrap();
+		fmp4.decode(&mut track_fragment(2, 0, 20, &video).as_slice()).unwrap();
+
+		// The audio fragment is split so both tracks start a group at the cut.
+		for name in fmp4.track_names().map(str::to_string).collect::<Vec<_>>() {
+			let points: Vec<_> = fmp4
+				.seek_index(&name)
+				.unwrap()
+				.points()
+				.map(|point| point.timestamp)
+				.collect();
+			assert_eq!(points, [ms(0), ms(40)], "{name}");
+		}
+
+		// The cut is only applied once.
+		fmp4.decode(&mut track_fragment(1, 3840, 960, &audio).as_slice())
+			.unwrap();
+		let name = fmp4.track_names().next().unwrap().to_string();
+		assert_eq!(fmp4.seek_index(&name).unwrap().len(), 3);
+	}
+
+	#[test]
+	fn validate() {
+		let mut buf = [init_segment(), fragment()].concat();
+		let info = crate::import::Decoder::validate(crate::import::DecoderFormat::Fmp4, &mut buf.as_slice()).unwrap();
+		assert_eq!(info.audio.len(), 1);
+		assert_eq!(info.audio[0].codec, AudioCodec::Opus);
+		assert_eq!(info.frames, 2);
+
+		// A truncated fragment is an error, rather than silently ignored.
+		buf.pop();
+		assert!(crate::import::Decoder::validate(crate::import::DecoderFormat::Fmp4, &mut buf.as_slice()).is_err());
+	}
+
+	#[test]
+	fn dry_run_creates_no_tracks() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+
+		let mut fmp4 = Fmp4::new(broadcast, catalog.clone(), Fmp4Config::default());
+		fmp4.set_options(ImportOptions {
+			dry_run: true,
+			..Default::default()
+		});
+		fmp4.decode(&mut [init_segment(), fragment()].concat().as_slice())
+			.unwrap();
+
+		// Like the other importers, there's no track name without a track.
+		assert!(fmp4.is_initialized());
+		assert_eq!(MediaDecoder::track_name(&fmp4), None);
+		assert_eq!(fmp4.media_info().unwrap().frames, 2);
+		assert!(catalog.lock().audio.renditions.is_empty());
+	}
+
+	#[test]
+	fn moov_replaces_tracks() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+
+		let mut fmp4 = Fmp4::new(broadcast, catalog.clone(), Fmp4Config::default());
+		fmp4.decode(&mut init_segment().as_slice()).unwrap();
+		fmp4.decode(&mut encode_moov(vec![vp8_trak(1)]).as_slice()).unwrap();
+
+		let guard = catalog.lock();
+		assert!(guard.audio.renditions.is_empty());
+		assert_eq!(guard.video.renditions.len(), 1);
+		drop(guard);
+
+		drop(fmp4);
+		assert!(catalog.lock().video.renditions.is_empty());
+	}
 }
@@ -1,4 +1,6 @@
-use super::annexb::{NalIterator, START_CODE};
+use super::annexb::{NalIterator, START_CODE, SplitTimestamps};
+use super::output::{Output, TrackConfig};
+use super::{DecoderBuf, ImportOptions, Importer, MediaDecoder, MediaInfo, SeekIndex, ensure_frame_size};
 
 use anyhow::Context;
 use buf_list::BufList;
@@ -8,14 +10,8 @@ use scuffle_h265::{NALUnitType, SpsNALUnit};
 /// A decoder for H.265 with inline SPS/PPS.
 /// Only supports single layer streams, ignores VPS.
 pub struct Hev1 {
-	// The broadcast being produced.
-	broadcast: moq_lite::BroadcastProducer,
-
-	// The catalog being produced.
-	catalog: hang::CatalogProducer,
-
-	// The track being produced.
-	track: Option<hang::container::OrderedProducer>,
+	// The track being produced, and the options.
+	output: Output,
 
 	// Whether the track has been initialized.
 	// If it changes, then we'll reinitialize with a new track.
@@ -24,25 +20,63 @@ pub struct Hev1 {
 	// The current frame being built.
 	current: Frame,
 
+	// Steps the timestamp of each access unit split from a single buffer.
+	split: SplitTimestamps,
+
 	// Used to compute wall clock timestamps if needed.
 	zero: Option<tokio::time::Instant>,
+
+	// The number of bytes consumed from the stream, used to report the location of errors.
+	offset: u64,
 }
 
 impl Hev1 {
 	pub fn new(broadcast: moq_lite::BroadcastProducer, catalog: hang::CatalogProducer) -> Self {
 		Self {
-			broadcast,
-			catalog,
-			track: None,
+			output: Output::new(broadcast, catalog),
 			config: None,
 			current: Default::default(),
+			split: Default::default(),
 			zero: None,
+			offset: 0,
 		}
 	}
 
+	/// Advertise a fixed jitter in the catalog, used by the player to size its jitter buffer.
+	///
+	/// This only applies to tracks created after this call.
+	pub fn set_jitter(&mut self, jitter: Option<std::time::Duration>) {
+		self.output.set_jitter(jitter);
+	}
+
+	/// The options used by this importer.
+	pub fn options(&self) -> &ImportOptions {
+		self.output.options()
+	}
+
+	/// Replace every option at once, see [ImportOptions].
+	///
+	/// This only applies to tracks created after this call, so call it before decoding.
+	pub fn set_options(&mut self, options: ImportOptions) {
+		self.output.set_options(options);
+	}
+
+	/// The seek index for the current track, or [None] if not enabled.
+	///
+	/// The index is reset when the SPS changes and a new track is created.
+	pub fn seek_index(&self) -> Option<&SeekIndex> {
+		self.output.seek_index()
+	}
+
+	/// The media seen so far in dry run mode, or [None] if not enabled.
+	pub fn media_info(&self) -> Option<&MediaInfo> {
+		self.output.media_info()
+	}
+
 	fn init(&mut self, sps: &SpsNALUnit) -> anyhow::Result<()> {
 		let profile = &sps.rbsp.profile_tier_level.general_profile;
 		let vui_data = sps.rbsp.vui_parameters.as_ref().map(VuiData::new).unwrap_or_default();
+		self.split.set_frame_duration(vui_data.frame_duration);
 
 		let config = hang::catalog::VideoConfig {
 			coded_width: Some(sps.rbsp.cropped_width() as u32),
@@ -64,7 +98,7 @@ impl Hev1 {
 			display_ratio_height: vui_data.display_ratio_height,
 			optimize_for_latency: None,
 			container: hang::catalog::Container::Legacy,
-			jitter: None,
+			jitter: self.output.jitter()?,
 		};
 
 		if let Some(old) = &self.config
@@ -73,27 +107,18 @@ impl Hev1 {
 			return Ok(());
 		}
 
-		let mut catalog = self.catalog.lock();
-
-		if let Some(track) = &self.track.take() {
-			tracing::debug!(name = ?track.info.name, "reinitializing track");
-			catalog.video.remove_track(&track.info);
-		}
-
-		let track = catalog.video.create_track("hev1", config.clone());
-		tracing::debug!(name = ?track.name, ?config, "starting track");
-
-		let track = self.broadcast.create_track(track);
-
+		self.output.replace_track("hev1", TrackConfig::Video(config.clone()));
 		self.config = Some(config);
-		self.track = Some(track.into());
 
 		Ok(())
 	}
 
 	/// Initialize the decoder with SPS/PPS and other non-slice NALs.
 	pub fn initialize<T: Buf + AsRef<[u8]>>(&mut self, buf: &mut T) -> anyhow::Result<()> {
-		let mut nals = NalIterator::new(buf);
+		let remaining = buf.remaining();
+		let mut nals = NalIterator::new(buf)
+			.with_offset(self.offset)
+			.with_max_size(self.output.max_frame_size());
 
 		while let Some(nal) = nals.next().transpose()? {
 			self.decode_nal(nal, None)?;
@@ -103,6 +128,8 @@ impl Hev1 {
 			self.decode_nal(nal, None)?;
 		}
 
+		self.offset += remaining as u64;
+
 		Ok(())
 	}
 
@@ -120,17 +147,26 @@ impl Hev1 {
 		let pts = self.pts(pts)?;
 
 		// Iterate over the NAL units in the buffer based on start codes.
-		let nals = NalIterator::new(buf);
+		let mut nals = NalIterator::new(buf)
+			.with_offset(self.offset)
+			.with_max_size(self.output.max_frame_size());
 
-		for nal in nals {
-			self.decode_nal(nal?, Some(pts))?;
+		while let Some(nal) = nals.next().transpose()? {
+			self.decode_nal(nal, Some(pts))?;
 		}
 
+		self.offset = nals.offset();
+
 		Ok(())
 	}
 
 	/// Decode all data in the buffer, assuming the buffer contains (the rest of) a frame.
 	///
+	/// If the buffer contains multiple access units, they are split into separate frames using
+	/// the AUD NAL or the first slice of each picture. The first frame uses the given timestamp,
+	/// and each frame after it is stepped by the frame duration from the SPS timing info.
+	/// If the SPS has no timing info, every frame uses the same timestamp and a warning is logged.
+	///
 	/// Unlike [Self::decode_stream], this is called when we know NAL boundaries.
 	/// This can avoid a frame of latency just waiting for the next frame's start code.
 	/// This can also be used when EOF is detected to flush the final frame.
@@ -142,8 +178,13 @@ impl Hev1 {
 		pts: Option<hang::container::Timestamp>,
 	) -> anyhow::Result<()> {
 		let pts = self.pts(pts)?;
+		let remaining = buf.remaining();
+		self.split.start(pts);
+
 		// Iterate over the NAL units in the buffer based on start codes.
-		let mut nals = NalIterator::new(buf);
+		let mut nals = NalIterator::new(buf)
+			.with_offset(self.offset)
+			.with_max_size(self.output.max_frame_size());
 
 		// Iterate over each NAL that is followed by a start code.
 		while let Some(nal) = nals.next().transpose()? {
@@ -155,8 +196,11 @@ impl Hev1 {
 			self.decode_nal(nal, Some(pts))?;
 		}
 
+		self.offset += remaining as u64;
+
 		// Flush the frame if we read a slice.
 		self.maybe_start_frame(Some(pts))?;
+		self.split.finish();
 
 		Ok(())
 	}
@@ -194,6 +238,11 @@ impl Hev1 {
 			| NALUnitType::BlaWRadl
 			| NALUnitType::BlaWLp
 			| NALUnitType::CraNut => {
+				// first_slice_segment_in_pic_flag, splits back-to-back IRAP pictures without an AUD.
+				if nal.get(2).context("NAL unit is too short")? & 0x80 != 0 {
+					self.maybe_start_frame(pts)?;
+				}
+
 				self.current.contains_idr = true;
 				self.current.contains_slice = true;
 			}
@@ -220,6 +269,9 @@ impl Hev1 {
 		// Rather than keeping the original size of the start code, we replace it with a 4 byte start code.
 		// It's just marginally easier and potentially more efficient down the line (JS player with MSE).
 		// NOTE: This is ref-counted and static, so it's extremely cheap to clone.
+		let size = self.current.chunks.remaining() + START_CODE.len() + nal.len();
+		ensure_frame_size(size, self.output.max_frame_size())?;
+
 		self.current.chunks.push_chunk(START_CODE.clone());
 		self.current.chunks.push_chunk(nal);
 
@@ -232,8 +284,8 @@ impl Hev1 {
 			return Ok(());
 		}
 
-		let track = self.track.as_mut().context("expected SPS before any frames")?;
-		let pts = pts.context("missing timestamp")?;
+		anyhow::ensure!(self.output.is_initialized(), "expected SPS before any frames");
+		let pts = self.split.next(pts.context("missing timestamp")?)?;
 
 		let payload = std::mem::take(&mut self.current.chunks);
 		let frame = hang::container::Frame {
@@ -242,7 +294,7 @@ impl Hev1 {
 			payload,
 		};
 
-		track.write(frame)?;
+		self.output.write(frame)?;
 
 		self.current.contains_idr = false;
 		self.current.contains_slice = false;
@@ -251,7 +303,21 @@ impl Hev1 {
 	}
 
 	pub fn is_initialized(&self) -> bool {
-		self.track.is_some()
+		self.output.is_initialized()
+	}
+
+	/// The name of the track in the broadcast, or [None] if not initialized yet.
+	///
+	/// A new track is created if the SPS changes, so this may change after each decode.
+	pub fn track_name(&self) -> Option<&str> {
+		self.output.track_name()
+	}
+
+	/// The track being produced, or [None] if not initialized yet.
+	///
+	/// This can be used to subscribe to the track locally via [hang::container::OrderedProducer::consume].
+	pub fn track(&self) -> Option<&hang::container::OrderedProducer> {
+		self.output.track()
 	}
 
 	fn pts(&mut self, hint: Option<hang::container::Timestamp>) -> anyhow::Result<hang::container::Timestamp> {
@@ -266,12 +332,51 @@ impl Hev1 {
 	}
 }
 
-impl Drop for Hev1 {
-	fn drop(&mut self) {
-		if let Some(track) = &self.track {
-			tracing::debug!(name = ?track.info.name, "ending track");
-			self.catalog.lock().video.remove_track(&track.info);
-		}
+impl MediaDecoder for Hev1 {
+	fn initialize(&mut self, mut buf: &mut dyn DecoderBuf) -> anyhow::Result<()> {
+		Hev1::initialize(self, &mut buf)
+	}
+
+	fn decode_frame(
+		&mut self,
+		mut buf: &mut dyn DecoderBuf,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		Hev1::decode_frame(self, &mut buf, pts)
+	}
+
+	fn decode_stream(
+		&mut self,
+		mut buf: &mut dyn DecoderBuf,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		Hev1::decode_stream(self, &mut buf, pts)
+	}
+
+	fn is_initialized(&self) -> bool {
+		Hev1::is_initialized(self)
+	}
+
+	fn track_name(&self) -> Option<&str> {
+		Hev1::track_name(self)
+	}
+}
+
+impl Importer for Hev1 {
+	fn options(&self) -> &ImportOptions {
+		Hev1::options(self)
+	}
+
+	fn set_options(&mut self, options: ImportOptions) {
+		Hev1::set_options(self, options)
+	}
+
+	fn seek_index(&self) -> Option<&SeekIndex> {
+		Hev1::seek_index(self)
+	}
+
+	fn media_info(&self) -> Option<&MediaInfo> {
+		Hev1::media_info(self)
 	}
 }
 
@@ -297,17 +402,21 @@ struct Frame {
 #[derive(Default)]
 struct VuiData {
 	framerate: Option<f64>,
+	// The duration of each frame as (ticks, time_scale).
+	frame_duration: Option<(u64, u64)>,
 	display_ratio_width: Option<u32>,
 	display_ratio_height: Option<u32>,
 }
 
 impl VuiData {
 	fn new(vui: &scuffle_h265::VuiParameters) -> Self {
-		// FPS = time_scale / num_units_in_tick
-		let framerate = vui
+		let frame_duration = vui
 			.vui_timing_info
 			.as_ref()
-			.map(|t| t.time_scale.get() as f64 / t.num_units_in_tick.get() as f64);
+			.map(|t| (t.num_units_in_tick.get() as u64, t.time_scale.get() as u64));
+
+		// FPS = time_scale / num_units_in_tick
+		let framerate = frame_duration.map(|(ticks, time_scale)| time_scale as f64 / ticks as f64);
 
 		let (display_ratio_width, display_ratio_height) = match &vui.aspect_ratio_info {
 			// Extended SAR has explicit arbitrary values for width and height.
@@ -322,6 +431,7 @@ impl VuiData {
 
 		VuiData {
 			framerate,
+			frame_duration,
 			display_ratio_width,
 			display_ratio_height,
 		}
@@ -351,3 +461,171 @@ fn aspect_ratio_from_idc(idc: scuffle_h265::AspectRatioIdc) -> Option<(u32, u32)
 		_ => None, // Reserved
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::import::AnnexBError;
+
+	// Main profile, 1920x1080, recorded with OBS.
+	const SPS: &[u8] = &[
+		0, 0, 0, 1, 0x42, 0x01, 0x01, 0x01, 0x40, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03,
+		0x00, 0x78, 0xa0, 0x03, 0xc0, 0x80, 0x11, 0x07, 0xcb, 0x96, 0xb4, 0xa4, 0x25, 0x92, 0xe3, 0x01, 0x6a, 0x02,
+		0x02, 0x02, 0x08, 0x00, 0x00, 0x03, 0x00, 0x08, 0x00, 0x00, 0x03, 0x00, 0xf3, 0x00, 0x2e, 0xf2, 0x88, 0x00,
+		0x02, 0x62, 0x5a, 0x00, 0x00, 0x13, 0x12, 0xd0, 0x20,
+	];
+	const AUD: &[u8] = &[0, 0, 0, 1, 0x46, 0x01, 0x50];
+	// IDR_W_RADL with first_slice_segment_in_pic_flag set.
+	const IDR: &[u8] = &[0, 0, 0, 1, 0x26, 0x01, 0xaf, 0x10];
+	// Another slice segment of the same IDR picture.
+	const IDR_SEGMENT: &[u8] = &[0, 0, 0, 1, 0x26, 0x01, 0x2f, 0x10];
+	// TRAIL_R with first_slice_segment_in_pic_flag set.
+	const TRAIL: &[u8] = &[0, 0, 0, 1, 0x02, 0x01, 0xd0, 0x10];
+
+	fn setup() -> (Hev1, hang::container::OrderedConsumer) {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut hev1 = Hev1::new(broadcast, catalog);
+
+		let mut init = Bytes::from_static(SPS);
+		hev1.initialize(&mut init).unwrap();
+
+		let consumer = hev1.track().unwrap().consume(std::time::Duration::from_secs(1));
+		(hev1, consumer)
+	}
+
+	// Assert the next frame starts a new group with the expected size.
+	async fn assert_keyframe(consumer: &mut hang::container::OrderedConsumer, size: usize) {
+		let frame = consumer.read().await.unwrap().unwrap();
+		assert!(frame.keyframe, "expected a new group per access unit");
+		assert_eq!(frame.payload.num_bytes(), size);
+	}
+
+	#[tokio::test]
+	async fn decode_frame_splits_on_aud() {
+		let (mut hev1, mut consumer) = setup();
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+
+		let mut buf = Bytes::from([AUD, IDR, AUD, IDR].concat());
+		hev1.decode_frame(&mut buf, Some(pts)).unwrap();
+
+		// The SPS from initialize is prepended to the first frame.
+		assert_keyframe(&mut consumer, SPS.len() + AUD.len() + IDR.len()).await;
+		assert_keyframe(&mut consumer, AUD.len() + IDR.len()).await;
+	}
+
+	#[tokio::test]
+	async fn decode_frame_splits_back_to_back_irap() {
+		let (mut hev1, mut consumer) = setup();
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+
+		// The first picture has two slice segments, and the second picture has no AUD.
+		let mut buf = Bytes::from([AUD, IDR, IDR_SEGMENT, IDR].concat());
+		hev1.decode_frame(&mut buf, Some(pts)).unwrap();
+
+		assert_keyframe(&mut consumer, SPS.len() + AUD.len() + IDR.len() + IDR_SEGMENT.len()).await;
+		assert_keyframe(&mut consumer, IDR.len()).await;
+	}
+
+	#[tokio::test]
+	async fn decode_frame_steps_split_timestamps() {
+		let (mut hev1, mut consumer) = setup();
+		let pts = hang::container::Timestamp::from_secs(1).unwrap();
+
+		// The SPS advertises 30 fps.
+		let mut buf = Bytes::from([AUD, IDR, AUD, TRAIL, AUD, TRAIL].concat());
+		hev1.decode_frame(&mut buf, Some(pts)).unwrap();
+
+		let mut timestamps = Vec::new();
+		for _ in 0..3 {
+			timestamps.push(consumer.read().await.unwrap().unwrap().timestamp.as_micros());
+		}
+		assert_eq!(timestamps, [1_000_000, 1_033_333, 1_066_666]);
+	}
+
+	#[tokio::test]
+	async fn seek_index_records_keyframes() {
+		let (mut hev1, mut consumer) = setup();
+		hev1.set_options(ImportOptions {
+			seek_index: Some(SeekIndex::new()),
+			..Default::default()
+		});
+
+		let ms = |ms: u64| hang::container::Timestamp::from_micros(ms * 1_000).unwrap();
+
+		for (pts, slice) in [(0, IDR), (33, TRAIL), (66, IDR), (100, TRAIL)] {
+			let mut buf = Bytes::from([AUD, slice].concat());
+			hev1.decode_frame(&mut buf, Some(ms(pts))).unwrap();
+		}
+
+		let mut keyframes = Vec::new();
+		for _ in 0..4 {
+			let frame = consumer.read().await.unwrap().unwrap();
+			if frame.keyframe {
+				keyframes.push(frame.timestamp);
+			}
+		}
+		assert_eq!(keyframes, [ms(0), ms(66)]);
+
+		let index = hev1.seek_index().unwrap();
+		let points: Vec<_> = index.points().map(|point| (point.timestamp, point.group)).collect();
+		assert_eq!(points, [(ms(0), 0), (ms(66), 1)]);
+	}
+
+	#[test]
+	fn error_reports_stream_offset() {
+		let (mut hev1, _consumer) = setup();
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+
+		let mut valid = Bytes::from([AUD, IDR].concat());
+		hev1.decode_frame(&mut valid, Some(pts)).unwrap();
+
+		// Followed by garbage where a start code is expected.
+		let mut corrupt = Bytes::from_static(&[0, 0, 2, 0x26, 0x01]);
+		let err = hev1.decode_frame(&mut corrupt, Some(pts)).unwrap_err();
+
+		// The offset includes the SPS passed to initialize.
+		let err = err.downcast_ref::<AnnexBError>().expect("missing offset");
+		assert_eq!(err.offset, (SPS.len() + AUD.len() + IDR.len()) as u64);
+		assert_eq!(err.bytes.as_ref(), &[0, 0, 2, 0x26]);
+	}
+
+	#[test]
+	fn max_frame_size() {
+		let (mut hev1, _consumer) = setup();
+		hev1.set_options(ImportOptions {
+			max_frame_size: SPS.len() + AUD.len(),
+			..Default::default()
+		});
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+
+		// Each NAL fits, but the frame with the SPS from initialize doesn't.
+		let mut buf = Bytes::from([AUD, IDR].concat());
+		let err = hev1.decode_frame(&mut buf, Some(pts)).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(hang::Error::InvalidFrame)));
+	}
+
+	#[test]
+	fn dry_run() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+		let mut hev1 = Hev1::new(broadcast, catalog.clone());
+		hev1.set_options(ImportOptions {
+			dry_run: true,
+			..Default::default()
+		});
+
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+		let mut buf = Bytes::from([SPS, AUD, IDR, AUD, TRAIL].concat());
+		hev1.decode_frame(&mut buf, Some(pts)).unwrap();
+
+		// Nothing was published.
+		assert!(hev1.is_initialized());
+		assert!(hev1.track().is_none());
+		assert!(catalog.lock().video.renditions.is_empty());
+
+		let info = hev1.media_info().unwrap();
+		assert_eq!(info.video[0].coded_width, Some(1920));
+		assert_eq!(info.frames, 2);
+	}
+}
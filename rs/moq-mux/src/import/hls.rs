@@ -459,6 +459,7 @@ impl Hls {
 		}
 
 		importer.decode(&mut bytes).context("failed to parse media segment")?;
+		anyhow::ensure!(bytes.is_empty(), "media segment was not fully consumed");
 		track.next_sequence = Some(sequence + 1);
 
 		Ok(())
@@ -0,0 +1,205 @@
+//! LOAS/LATM framing for AAC (ISO 14496-3 1.7).
+//!
+//! Each LOAS AudioSyncStream frame is an 11-bit syncword, a 13-bit length, and a LATM AudioMuxElement.
+//! The AudioMuxElement contains an optional StreamMuxConfig, which is usually only sent periodically.
+//! Only the common case is supported: a single program and layer with `frameLengthType == 0`.
+
+use super::asc::AudioSpecificConfig;
+use super::bits::BitReader;
+
+use anyhow::Context;
+use bytes::Bytes;
+
+/// The 11-bit LOAS AudioSyncStream syncword.
+const SYNC: u16 = 0x2B7;
+
+/// The size of the LOAS header: syncword (11 bits) + audioMuxLengthBytes (13 bits).
+const HEADER_SIZE: usize = 3;
+
+/// Returns true if the buffer starts with a LOAS syncword.
+pub fn is_loas(buf: &[u8]) -> bool {
+	buf.len() >= 2 && (((buf[0] as u16) << 3) | ((buf[1] as u16) >> 5)) == SYNC
+}
+
+/// Returns the size of the next LOAS frame, including the header, or [None] if more data is needed.
+pub fn frame_size(buf: &[u8]) -> anyhow::Result<Option<usize>> {
+	if buf.len() < HEADER_SIZE {
+		return Ok(None);
+	}
+
+	anyhow::ensure!(is_loas(buf), "missing LOAS syncword");
+
+	let length = (((buf[1] & 0x1f) as usize) << 8) | buf[2] as usize;
+	let size = HEADER_SIZE + length;
+
+	Ok((buf.len() >= size).then_some(size))
+}
+
+/// The result of parsing a single AudioMuxElement.
+pub struct AudioMuxElement {
+	/// The AudioSpecificConfig, only set when it differs from the previous StreamMuxConfig.
+	pub config: Option<Bytes>,
+
+	/// The raw AAC payloads, one per sub-frame.
+	pub payloads: Vec<Bytes>,
+}
+
+// The parts of the StreamMuxConfig needed to parse subsequent frames.
+struct StreamMuxConfig {
+	asc: Bytes,
+	num_sub_frames: u32,
+	other_data_bits: u32,
+}
+
+/// Parses LOAS frames, caching the StreamMuxConfig for frames with `useSameStreamMux`.
+#[derive(Default)]
+pub struct Loas {
+	config: Option<StreamMuxConfig>,
+}
+
+impl Loas {
+	/// Parse a complete LOAS frame, as sized by [frame_size].
+	pub fn decode(&mut self, frame: &[u8]) -> anyhow::Result<AudioMuxElement> {
+		anyhow::ensure!(frame.len() >= HEADER_SIZE, "LOAS frame is too short");
+		let mut r = BitReader::new(&frame[HEADER_SIZE..]);
+
+		let use_same_stream_mux = r.read_bool()?;
+
+		let mut changed = None;
+		if !use_same_stream_mux {
+			let config = stream_mux_config(&mut r)?;
+			if self.config.as_ref().map(|old| &old.asc) != Some(&config.asc) {
+				changed = Some(config.asc.clone());
+			}
+			self.config = Some(config);
+		}
+
+		let config = self.config.as_ref().context("missing StreamMuxConfig")?;
+
+		let mut payloads = Vec::with_capacity(config.num_sub_frames as usize + 1);
+		for _ in 0..=config.num_sub_frames {
+			// PayloadLengthInfo: MuxSlotLengthBytes, terminated by a byte that isn't 255.
+			let mut size = 0;
+			loop {
+				let tmp = r.read(8)?;
+				size += tmp as usize;
+				if tmp != 255 {
+					break;
+				}
+			}
+
+			payloads.push(r.read_bytes(size)?);
+		}
+
+		r.skip(config.other_data_bits as usize)?;
+
+		Ok(AudioMuxElement {
+			config: changed,
+			payloads,
+		})
+	}
+}
+
+fn stream_mux_config(r: &mut BitReader) -> anyhow::Result<StreamMuxConfig> {
+	let audio_mux_version = r.read_bool()?;
+	let audio_mux_version_a = audio_mux_version && r.read_bool()?;
+	anyhow::ensure!(!audio_mux_version_a, "audioMuxVersionA is not supported");
+
+	if audio_mux_version {
+		latm_value(r)?; // taraBufferFullness
+	}
+
+	let all_streams_same_time_framing = r.read_bool()?;
+	anyhow::ensure!(
+		all_streams_same_time_framing,
+		"allStreamsSameTimeFraming=0 is not supported"
+	);
+
+	let num_sub_frames = r.read(6)?;
+	let num_program = r.read(4)?;
+	anyhow::ensure!(num_program == 0, "multiple LATM programs are not supported");
+	let num_layer = r.read(3)?;
+	anyhow::ensure!(num_layer == 0, "multiple LATM layers are not supported");
+
+	// useSameConfig is implicitly false for the first program and layer.
+	let asc = if audio_mux_version {
+		let size = latm_value(r)? as usize;
+		let start = r.position();
+		r.skip(size)?;
+		r.slice(start, size)
+	} else {
+		// The size isn't signaled, so we have to parse it.
+		let start = r.position();
+		AudioSpecificConfig::skip(r)?;
+		r.slice(start, r.position() - start)
+	};
+
+	let frame_length_type = r.read(3)?;
+	anyhow::ensure!(
+		frame_length_type == 0,
+		"unsupported LATM frameLengthType: {frame_length_type}"
+	);
+	r.skip(8)?; // latmBufferFullness
+
+	let other_data_present = r.read_bool()?;
+	let other_data_bits = match other_data_present {
+		false => 0,
+		true if audio_mux_version => latm_value(r)?,
+		true => {
+			let mut bits = 0u32;
+			loop {
+				let escape = r.read_bool()?;
+				bits = bits.checked_mul(256).context("otherDataLenBits overflow")? + r.read(8)?;
+				if !escape {
+					break bits;
+				}
+			}
+		}
+	};
+
+	if r.read_bool()? {
+		r.skip(8)?; // crcCheckSum
+	}
+
+	Ok(StreamMuxConfig {
+		asc,
+		num_sub_frames,
+		other_data_bits,
+	})
+}
+
+// LatmGetValue()
+fn latm_value(r: &mut BitReader) -> anyhow::Result<u32> {
+	let bytes = r.read(2)?;
+	let mut value = 0;
+	for _ in 0..=bytes {
+		value = (value << 8) | r.read(8)?;
+	}
+	Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn frame_size_needs_more_data() {
+		// Sync + length of 4, but only 2 bytes of the element.
+		let buf = [0x56, 0xe0, 0x04, 0x00, 0x00];
+		assert!(is_loas(&buf));
+		assert_eq!(frame_size(&buf).unwrap(), None);
+		assert_eq!(frame_size(&[0x56, 0xe0, 0x04, 0, 0, 0, 0]).unwrap(), Some(7));
+	}
+
+	#[test]
+	fn frame_size_missing_sync() {
+		assert!(frame_size(&[0xff, 0xf1, 0x50]).is_err());
+	}
+
+	#[test]
+	fn same_stream_mux_requires_config() {
+		// useSameStreamMux=1 without a previous StreamMuxConfig.
+		let mut loas = Loas::default();
+		assert!(loas.decode(&[0x56, 0xe0, 0x02, 0x80, 0x00]).is_err());
+	}
+}
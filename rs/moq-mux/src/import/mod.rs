@@ -4,8 +4,8 @@
 //! It supports various container and codec formats, optionally enabled via feature flags.
 //!
 //! **Feature flags:**
-//! - `aac`: Raw AAC frames (not ADTS).
-//! - `opus`: Raw Opus frames (not Ogg).
+//! - `aac`: Raw AAC frames, optionally length prefixed, or a LOAS/LATM stream (not ADTS).
+//! - `opus`: Raw Opus frames, optionally length prefixed (not Ogg).
 //! - `avc3`: H.264 with inline SPS/PPS.
 //! - `fmp4`: fMP4/CMAF container.
 //! - `hev1`: H.265 with inline SPS/PPS.
@@ -18,8 +18,12 @@
 mod aac;
 #[cfg(any(feature = "h264", feature = "h265"))]
 mod annexb;
+#[cfg(feature = "aac")]
+mod asc;
 #[cfg(feature = "h264")]
 mod avc3;
+#[cfg(any(feature = "aac", feature = "h264"))]
+mod bits;
 mod decoder;
 #[cfg(feature = "mp4")]
 mod fmp4;
@@ -27,11 +31,26 @@ mod fmp4;
 mod hev1;
 #[cfg(feature = "hls")]
 mod hls;
+#[cfg(feature = "aac")]
+mod loas;
 #[cfg(feature = "opus")]
 mod opus;
+#[cfg(any(
+	feature = "h264",
+	feature = "mp4",
+	feature = "h265",
+	feature = "aac",
+	feature = "opus"
+))]
+mod output;
+#[cfg(any(feature = "aac", feature = "opus"))]
+mod prefix;
+mod seek;
 
 #[cfg(feature = "aac")]
 pub use aac::*;
+#[cfg(any(feature = "h264", feature = "h265"))]
+pub use annexb::AnnexBError;
 #[cfg(feature = "h264")]
 pub use avc3::*;
 pub use decoder::*;
@@ -43,3 +62,6 @@ pub use hev1::*;
 pub use hls::*;
 #[cfg(feature = "opus")]
 pub use opus::*;
+#[cfg(any(feature = "aac", feature = "opus"))]
+pub use prefix::*;
+pub use seek::*;
@@ -1,31 +1,107 @@
+use super::output::{Output, TrackConfig};
+use super::{
+	DecoderBuf, ImportOptions, Importer, LengthPrefixed, MediaDecoder, MediaInfo, SeekIndex, ensure_frame_size,
+};
+
 use anyhow::Context;
 use buf_list::BufList;
 use bytes::Buf;
+use hang::container::Timestamp;
+
+// Packets this small carry no audio; the encoder emits them during silence when DTX is enabled.
+const DTX_MAX_SIZE: usize = 2;
 
 /// Opus decoder, initialized via a OpusHead. Does not support Ogg.
+///
+/// Raw Opus frames are decoded with [Self::decode], while length prefixed streams are decoded with [Self::decode_stream].
+///
+/// DTX (discontinuous transmission) packets are detected by their size and TOC byte.
+/// When using wall clock timestamps, the first one starts where the previous packet ended, and runs of them share a group.
 pub struct Opus {
-	broadcast: moq_lite::BroadcastProducer,
-	catalog: hang::CatalogProducer,
-	track: Option<hang::container::OrderedProducer>,
+	// The track being produced, named using the base name, and the options.
+	output: Output,
+
 	zero: Option<tokio::time::Instant>,
+
+	// The end of the previous packet, based on the duration in its TOC byte.
+	next: Option<Timestamp>,
+
+	// Whether the previous packet was a DTX packet.
+	dtx: bool,
+
+	// Start a new group at the first packet at or after this timestamp.
+	cut: Option<Timestamp>,
+
+	// The length prefix used to split a stream into packets.
+	length_prefixed: Option<LengthPrefixed>,
 }
 
 impl Opus {
 	pub fn new(broadcast: moq_lite::BroadcastProducer, catalog: hang::CatalogProducer) -> Self {
+		Self::new_named(broadcast, catalog, "audio")
+	}
+
+	/// Create a decoder that names its track using the given base name instead of "audio", ex. "commentary0.opus".
+	///
+	/// This distinguishes multiple audio importers on the same broadcast.
+	pub fn new_named(
+		broadcast: moq_lite::BroadcastProducer,
+		catalog: hang::CatalogProducer,
+		name: impl Into<String>,
+	) -> Self {
 		Self {
-			broadcast,
-			catalog,
-			track: None,
+			output: Output::named(broadcast, catalog, name),
 			zero: None,
+			next: None,
+			dtx: false,
+			cut: None,
+			length_prefixed: None,
 		}
 	}
 
+	/// Split the stream passed to [Self::decode_stream] using a length prefix before each packet.
+	pub fn set_length_prefixed(&mut self, length_prefixed: Option<LengthPrefixed>) {
+		self.length_prefixed = length_prefixed;
+	}
+
+	/// The options used by this importer.
+	pub fn options(&self) -> &ImportOptions {
+		self.output.options()
+	}
+
+	/// Replace every option at once, see [ImportOptions].
+	///
+	/// This only applies to tracks created after this call, so call it before decoding.
+	pub fn set_options(&mut self, options: ImportOptions) {
+		self.output.set_options(options);
+	}
+
+	/// The seek index for the current track, or [None] if not enabled.
+	///
+	/// Every packet that starts a group is recorded, and the index is reset when a new track is created.
+	pub fn seek_index(&self) -> Option<&SeekIndex> {
+		self.output.seek_index()
+	}
+
+	/// The media seen so far in dry run mode, or [None] if not enabled.
+	pub fn media_info(&self) -> Option<&MediaInfo> {
+		self.output.media_info()
+	}
+
+	/// Advertise a fixed jitter in the catalog, used by the player to size its jitter buffer.
+	///
+	/// This only applies to tracks created after this call.
+	pub fn set_jitter(&mut self, jitter: Option<std::time::Duration>) {
+		self.output.set_jitter(jitter);
+	}
+
 	pub fn initialize<T: Buf>(&mut self, buf: &mut T) -> anyhow::Result<()> {
 		// Parse OpusHead (https://datatracker.ietf.org/doc/html/rfc7845#section-5.1)
 		//  - Verifies "OpusHead" magic signature
 		//  - Reads channel count
 		//  - Reads sample rate
-		//  - Ignores pre-skip, gain, channel mapping for now
+		//  - Reads output gain
+		//  - Ignores pre-skip, channel mapping for now
 
 		anyhow::ensure!(buf.remaining() >= 19, "OpusHead must be at least 19 bytes");
 		const OPUS_HEAD: u64 = u64::from_be_bytes(*b"OpusHead");
@@ -37,13 +113,15 @@ impl Opus {
 		buf.advance(2); // Skip pre-skip (lol)
 		let sample_rate = buf.get_u32_le();
 
-		// Skip gain, channel mapping until if/when we support them
+		// The output gain is in Q7.8 dB, omitted from the catalog when zero.
+		let gain = buf.get_i16_le();
+		let gain_db = (gain != 0).then(|| gain as f32 / 256.0);
+
+		// Skip channel mapping until if/when we support it
 		if buf.remaining() > 0 {
 			buf.advance(buf.remaining());
 		}
 
-		let mut catalog = self.catalog.lock();
-
 		let config = hang::catalog::AudioConfig {
 			codec: hang::catalog::AudioCodec::Opus,
 			sample_rate,
@@ -51,61 +129,523 @@ impl Opus {
 			bitrate: None,
 			description: None,
 			container: hang::catalog::Container::Legacy,
-			jitter: None,
+			jitter: self.output.jitter()?,
+			gain_db,
 		};
 
-		let track = catalog.audio.create_track("opus", config.clone());
-		tracing::debug!(name = ?track.name, ?config, "starting track");
+		self.output.replace_track("opus", TrackConfig::Audio(config));
 
-		let track = self.broadcast.create_track(track);
-		self.track = Some(track.into());
+		// The new track has no groups yet, so its first packet must be a keyframe, even during a DTX run.
+		self.next = None;
+		self.dtx = false;
+		self.cut = None;
 
 		Ok(())
 	}
 
 	pub fn decode<T: Buf>(&mut self, buf: &mut T, pts: Option<hang::container::Timestamp>) -> anyhow::Result<()> {
-		let pts = self.pts(pts)?;
-		let track = self.track.as_mut().context("not initialized")?;
-
 		// Create a BufList at chunk boundaries, potentially avoiding allocations.
 		let mut payload = BufList::new();
 		while !buf.chunk().is_empty() {
 			payload.push_chunk(buf.copy_to_bytes(buf.chunk().len()));
 		}
 
+		self.decode_bytes(payload, pts)
+	}
+
+	/// Decode a frame that is already reference counted, without copying the payload.
+	///
+	/// Prefer this over [Self::decode] when the frame is a [bytes::Bytes] or [BufList].
+	pub fn decode_bytes<B: Into<BufList>>(
+		&mut self,
+		payload: B,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		let payload: BufList = payload.into();
+		ensure_frame_size(payload.num_bytes(), self.output.max_frame_size())?;
+
+		// Copy the TOC byte and frame count, which may span chunks.
+		let header: Vec<u8> = payload.iter().flat_map(|chunk| chunk.iter().copied()).take(2).collect();
+
+		let dtx = is_dtx(&header, payload.num_bytes());
+		let duration = packet_duration(&header);
+
+		let pts = self.pts(pts, dtx)?;
+		anyhow::ensure!(self.output.is_initialized(), "not initialized");
+
+		let cut = self.cut.is_some_and(|cut| pts >= cut);
+		if cut {
+			self.cut = None;
+		}
+
 		let frame = hang::container::Frame {
 			timestamp: pts,
-			keyframe: true, // Audio frames are always keyframes
+			// Audio frames are always keyframes, except we coalesce runs of DTX packets into a single group.
+			keyframe: cut || !(dtx && self.dtx),
 			payload,
 		};
 
-		track.write(frame)?;
-		track.flush()?; // Flush the current group because we know the next frame will be a keyframe.
+		// Only counted in dry run mode.
+		if let Some(track) = self.output.write(frame)?
+			&& !dtx
+		{
+			track.flush()?; // Flush the current group because we know the next frame will be a keyframe.
+		}
+
+		self.next = duration.and_then(|duration| pts.checked_add(duration).ok());
+		self.dtx = dtx;
+
+		Ok(())
+	}
+
+	/// Start a new group at the first packet at or after the given timestamp, see [super::Decoder::cut_group].
+	///
+	/// This splits a run of DTX packets, which are otherwise coalesced into a single group.
+	pub fn cut_group(&mut self, timestamp: Timestamp) {
+		self.cut = Some(timestamp);
+	}
+
+	/// Decode as many length prefixed packets as possible from the given buffer.
+	///
+	/// This requires [Self::set_length_prefixed] and [Self::initialize] to be called first.
+	/// If the buffer ends with a partial packet, it is left unconsumed and more data is needed.
+	pub fn decode_stream<T: Buf + AsRef<[u8]>>(
+		&mut self,
+		buf: &mut T,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		let prefix = self
+			.length_prefixed
+			.context("Opus stream decoding requires a length prefix")?;
+
+		while let Some(packet) = prefix.next(buf, self.output.max_frame_size())? {
+			self.decode_bytes(packet, pts)?;
+		}
 
 		Ok(())
 	}
 
 	pub fn is_initialized(&self) -> bool {
-		self.track.is_some()
+		self.output.is_initialized()
+	}
+
+	/// The name of the track in the broadcast, or [None] if not initialized yet.
+	pub fn track_name(&self) -> Option<&str> {
+		self.output.track_name()
 	}
 
-	fn pts(&mut self, hint: Option<hang::container::Timestamp>) -> anyhow::Result<hang::container::Timestamp> {
+	/// The track being produced, or [None] if not initialized yet.
+	///
+	/// This can be used to subscribe to the track locally via [hang::container::OrderedProducer::consume].
+	pub fn track(&self) -> Option<&hang::container::OrderedProducer> {
+		self.output.track()
+	}
+
+	fn pts(&mut self, hint: Option<Timestamp>, dtx: bool) -> anyhow::Result<Timestamp> {
 		if let Some(pts) = hint {
 			return Ok(pts);
 		}
 
+		// The silence starts where the speech ended, regardless of when the first DTX packet arrived.
+		// Later DTX packets are sent periodically, so they use the wall clock like everything else.
+		if dtx
+			&& !self.dtx
+			&& let Some(next) = self.next
+		{
+			return Ok(next);
+		}
+
 		let zero = self.zero.get_or_insert_with(tokio::time::Instant::now);
-		Ok(hang::container::Timestamp::from_micros(
-			zero.elapsed().as_micros() as u64
-		)?)
+		let pts = Timestamp::from_micros(zero.elapsed().as_micros() as u64)?;
+
+		// Never overlap the previous packet, otherwise a late DTX packet could cause timestamps to go backwards.
+		Ok(self.next.map_or(pts, |next| pts.max(next)))
 	}
 }
 
-impl Drop for Opus {
-	fn drop(&mut self) {
-		if let Some(track) = self.track.take() {
-			tracing::debug!(name = ?track.info.name, "ending track");
-			self.catalog.lock().audio.remove_track(&track.info);
+// Returns true if the packet is only a TOC byte (and frame length or count) with empty frames, as sent during DTX.
+fn is_dtx(header: &[u8], size: usize) -> bool {
+	if size > DTX_MAX_SIZE {
+		return false;
+	}
+
+	match (header.first().map(|toc| toc & 0x03), header.get(1)) {
+		// One or two frames, signaled by the TOC byte alone.
+		(Some(0 | 1), None) => true,
+		// Two frames, where the first (and therefore the second) is empty.
+		(Some(2), Some(0)) => true,
+		// An arbitrary number of empty frames without padding.
+		(Some(3), Some(count)) => count & 0x40 == 0 && count & 0x3f > 0,
+		_ => false,
+	}
+}
+
+// Returns the duration of an Opus packet based on its TOC byte (RFC 6716 3.1), or [None] if it's truncated.
+fn packet_duration(packet: &[u8]) -> Option<Timestamp> {
+	let toc = *packet.first()?;
+	let config = toc >> 3;
+
+	// The duration of each frame in units of 2.5ms.
+	let frame = match config {
+		// SILK-only: 10, 20, 40, 60ms
+		0..=11 => [4, 8, 16, 24][config as usize % 4],
+		// Hybrid: 10, 20ms
+		12..=15 => [4, 8][config as usize % 2],
+		// CELT-only: 2.5, 5, 10, 20ms
+		_ => [1, 2, 4, 8][config as usize % 4],
+	};
+
+	let count = match toc & 0x03 {
+		0 => 1,
+		1 | 2 => 2,
+		// An arbitrary number of frames, signaled in the next byte.
+		_ => packet.get(1)? & 0x3f,
+	};
+
+	Timestamp::from_micros(frame * count as u64 * 2_500).ok()
+}
+
+impl MediaDecoder for Opus {
+	fn initialize(&mut self, mut buf: &mut dyn DecoderBuf) -> anyhow::Result<()> {
+		Opus::initialize(self, &mut buf)
+	}
+
+	fn decode_frame(
+		&mut self,
+		mut buf: &mut dyn DecoderBuf,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		Opus::decode(self, &mut buf, pts)
+	}
+
+	fn decode_stream(
+		&mut self,
+		mut buf: &mut dyn DecoderBuf,
+		pts: Option<hang::container::Timestamp>,
+	) -> anyhow::Result<()> {
+		Opus::decode_stream(self, &mut buf, pts)
+	}
+
+	fn is_initialized(&self) -> bool {
+		Opus::is_initialized(self)
+	}
+
+	fn track_name(&self) -> Option<&str> {
+		Opus::track_name(self)
+	}
+}
+
+impl Importer for Opus {
+	fn options(&self) -> &ImportOptions {
+		Opus::options(self)
+	}
+
+	fn set_options(&mut self, options: ImportOptions) {
+		Opus::set_options(self, options)
+	}
+
+	fn seek_index(&self) -> Option<&SeekIndex> {
+		Opus::seek_index(self)
+	}
+
+	fn media_info(&self) -> Option<&MediaInfo> {
+		Opus::media_info(self)
+	}
+
+	fn cut_group(&mut self, timestamp: Timestamp) {
+		Opus::cut_group(self, timestamp)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use bytes::{BufMut, Bytes, BytesMut};
+
+	fn opus_head() -> Bytes {
+		opus_head_with_gain(0)
+	}
+
+	fn opus_head_with_gain(gain: i16) -> Bytes {
+		let mut head = BytesMut::new();
+		head.put_slice(b"OpusHead");
+		head.put_u8(1); // version
+		head.put_u8(2); // channels
+		head.put_u16_le(312); // pre-skip
+		head.put_u32_le(48_000); // sample rate
+		head.put_i16_le(gain); // output gain
+		head.put_u8(0); // channel mapping family
+		head.freeze()
+	}
+
+	#[tokio::test]
+	async fn decode_bytes_is_zero_copy() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut opus = Opus::new(broadcast, catalog);
+		opus.initialize(&mut opus_head()).unwrap();
+
+		let mut consumer = opus.track().unwrap().consume(std::time::Duration::from_secs(1));
+
+		let payload = Bytes::from(vec![0xfc, 0xff, 0xfe]);
+		let pts = hang::container::Timestamp::from_micros(0).unwrap();
+		opus.decode_bytes(payload.clone(), Some(pts)).unwrap();
+
+		let frame = consumer.read().await.unwrap().unwrap();
+		assert_eq!(frame.payload.num_chunks(), 1);
+		assert_eq!(frame.payload.get_chunk(0).unwrap().as_ptr(), payload.as_ptr());
+	}
+
+	#[test]
+	fn gain_reaches_catalog() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+		let mut opus = Opus::new(broadcast, catalog.clone());
+
+		// -2.5 dB in Q7.8
+		opus.initialize(&mut opus_head_with_gain(-640)).unwrap();
+
+		let json = catalog.lock().to_string().unwrap();
+		assert!(json.contains("\"gainDb\":-2.5"));
+
+		let parsed = hang::Catalog::from_str(&json).unwrap();
+		let config = parsed.audio.renditions.values().next().unwrap();
+		assert_eq!(config.gain_db, Some(-2.5));
+	}
+
+	#[test]
+	fn zero_gain_is_omitted() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+		let mut opus = Opus::new(broadcast, catalog.clone());
+		opus.initialize(&mut opus_head()).unwrap();
+
+		let json = catalog.lock().to_string().unwrap();
+		assert!(!json.contains("gainDb"));
+	}
+
+	#[test]
+	fn drop_with_catalog_locked() {
+		let broadcast = moq_lite::Broadcast::produce();
+		let mut catalog = hang::Catalog::default().produce();
+		let mut opus = Opus::new(broadcast, catalog.clone());
+		opus.initialize(&mut opus_head()).unwrap();
+
+		// Drop on another thread while the lock is held, failing instead of hanging if it blocks.
+		let guard = catalog.lock();
+		let (tx, rx) = std::sync::mpsc::channel();
+		std::thread::spawn(move || {
+			drop(opus);
+			tx.send(()).unwrap();
+		});
+		rx.recv_timeout(std::time::Duration::from_secs(5))
+			.expect("drop blocked on the catalog lock");
+
+		// The track removal is queued until the guard is dropped.
+		assert_eq!(guard.audio.renditions.len(), 1);
+		drop(guard);
+
+		assert!(catalog.lock().audio.renditions.is_empty());
+	}
+
+	// CELT-only, 20ms, a single frame.
+	const TOC: u8 = 0xfc;
+
+	fn setup() -> (Opus, hang::container::OrderedConsumer) {
+		let broadcast = moq_lite::Broadcast::produce();
+		let catalog = hang::Catalog::default().produce();
+		let mut opus = Opus::new(broadcast, catalog);
+		opus.initialize(&mut opus_head()).unwrap();
+
+		let consumer = opus.track().unwrap().consume(std::time::Duration::from_secs(10));
+		(opus, consumer)
+	}
+
+	#[tokio::test]
+	async fn decode_stream_length_prefixed() {
+		let (mut opus, mut consumer) = setup();
+		opus.set_length_prefixed(Some(LengthPrefixed::U16Be));
+		let pts = Timestamp::from_micros(0).unwrap();
+
+		// Two complete packets followed by a partial packet.
+		let mut data = vec![0x00, 0x03, TOC, 0x01, 0x02];
+		data.extend([0x00, 0x04, TOC, 0x03, 0x04, 0x05]);
+		data.extend([0x00, 0x04, TOC, 0x06]);
+
+		let mut buf = Bytes::from(data);
+		opus.decode_stream(&mut buf, Some(pts)).unwrap();
+		assert_eq!(buf.as_ref(), &[0x00, 0x04, TOC, 0x06]);
+
+		for expected in [&[TOC, 0x01, 0x02][..], &[TOC, 0x03, 0x04, 0x05]] {
+			let mut frame = consumer.read().await.unwrap().unwrap();
+			assert_eq!(
+				frame.payload.copy_to_bytes(frame.payload.remaining()).as_ref(),
+				expected
+			);
 		}
+
+		// The rest of the partial packet completes it.
+		let mut rest = buf.to_vec();
+		rest.extend([0x07, 0x08]);
+
+		let mut buf = Bytes::from(rest);
+		opus.decode_stream(&mut buf, Some(pts)).unwrap();
+		assert!(buf.is_empty());
+
+		let mut frame = consumer.read().await.unwrap().unwrap();
+		assert_eq!(
+			frame.payload.copy_to_bytes(frame.payload.remaining()).as_ref(),
+			&[TOC, 0x06, 0x07, 0x08]
+		);
+	}
+
+	#[test]
+	fn decode_stream_max_frame_size() {
+		let (mut opus, _consumer) = setup();
+		opus.set_length_prefixed(Some(LengthPrefixed::U16Be));
+		opus.set_options(ImportOptions {
+			max_frame_size: 1024,
+			..Default::default()
+		});
+
+		// A large packet under the limit is decoded once complete.
+		let mut data = vec![0x04, 0x00, TOC];
+		data.resize(2 + 1024, 0xab);
+
+		let mut buf = Bytes::from(data);
+		opus.decode_stream(&mut buf, None).unwrap();
+		assert!(buf.is_empty());
+
+		// A packet over the limit is rejected as soon as the prefix is read.
+		let mut buf = Bytes::from_static(&[0x04, 0x01, TOC]);
+		let err = opus.decode_stream(&mut buf, None).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(hang::Error::InvalidFrame)));
+	}
+
+	#[test]
+	fn cut_group_during_dtx() {
+		let (mut opus, _consumer) = setup();
+		let ms = |ms: u64| Timestamp::from_micros(ms * 1_000).unwrap();
+
+		// A run of DTX packets is coalesced into a single group, until the cut.
+		opus.cut_group(ms(40));
+
+		for (pts, group) in [(0, 0), (20, 0), (40, 1), (60, 1)] {
+			opus.decode_bytes(Bytes::from_static(&[TOC]), Some(ms(pts))).unwrap();
+			assert_eq!(opus.track().unwrap().group_sequence(), Some(group), "{pts}ms");
+		}
+	}
+
+	#[test]
+	fn decode_stream_requires_length_prefix() {
+		let (mut opus, _consumer) = setup();
+		let mut buf = Bytes::from_static(&[0x00, 0x01, TOC]);
+		assert!(opus.decode_stream(&mut buf, None).is_err());
+	}
+
+	#[test]
+	fn packet_duration_from_toc() {
+		let ms = |ms: u64| Some(Timestamp::from_micros(ms * 1_000).unwrap());
+
+		assert_eq!(packet_duration(&[TOC]), ms(20));
+		assert_eq!(packet_duration(&[0x00]), ms(10)); // SILK 10ms
+		assert_eq!(packet_duration(&[0x09]), ms(40)); // SILK 20ms, two frames
+		assert_eq!(packet_duration(&[0x78, 0x00]), ms(20)); // Hybrid 20ms
+		assert_eq!(
+			packet_duration(&[0x83, 0x03]),
+			Some(Timestamp::from_micros(7_500).unwrap())
+		); // CELT 2.5ms, three frames
+		assert_eq!(packet_duration(&[0x83]), None);
+		assert_eq!(packet_duration(&[]), None);
+	}
+
+	#[test]
+	fn dtx_from_size_and_toc() {
+		assert!(is_dtx(&[TOC], 1));
+		assert!(is_dtx(&[0x09], 1)); // SILK, two empty frames
+		assert!(is_dtx(&[0xfe, 0x00], 2)); // Two frames, the first is empty
+		assert!(is_dtx(&[0xff, 0x02], 2)); // Two empty frames, signaled by count
+
+		assert!(!is_dtx(&[], 0));
+		assert!(!is_dtx(&[TOC, 0xff], 2)); // A single one byte frame
+		assert!(!is_dtx(&[0xfe, 0x01], 2)); // Two frames, the second is truncated
+		assert!(!is_dtx(&[0xff, 0x00], 2)); // Zero frames
+		assert!(!is_dtx(&[0xff, 0x42], 2)); // Padding without a length
+		assert!(!is_dtx(&[0xff], 1)); // Missing the frame count
+		assert!(!is_dtx(&[TOC, 0xff, 0xfe], 3));
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn dtx_timestamps_are_gap_correct() {
+		let (mut opus, mut consumer) = setup();
+
+		opus.decode_bytes(Bytes::from_static(&[TOC, 0xff, 0xfe]), None).unwrap();
+
+		// The first DTX packet arrives late, but the silence starts when the previous packet ended.
+		tokio::time::advance(std::time::Duration::from_millis(25)).await;
+		opus.decode_bytes(Bytes::from_static(&[TOC]), None).unwrap();
+
+		// The next DTX packet uses the wall clock.
+		tokio::time::advance(std::time::Duration::from_millis(400)).await;
+		opus.decode_bytes(Bytes::from_static(&[TOC]), None).unwrap();
+
+		// Speech resumes after 400ms of silence.
+		tokio::time::advance(std::time::Duration::from_millis(400)).await;
+		opus.decode_bytes(Bytes::from_static(&[TOC, 0xff, 0xfe]), None).unwrap();
+
+		let mut timestamps = Vec::new();
+		for _ in 0..4 {
+			let frame = consumer.read().await.unwrap().unwrap();
+			timestamps.push(frame.timestamp.as_micros());
+		}
+
+		assert_eq!(timestamps, [0, 20_000, 425_000, 825_000]);
+	}
+
+	#[tokio::test]
+	async fn reinitialize_during_dtx() {
+		let (mut opus, _consumer) = setup();
+		let ms = |ms: u64| Some(Timestamp::from_micros(ms * 1_000).unwrap());
+
+		opus.decode_bytes(Bytes::from_static(&[TOC, 0xff, 0xfe]), ms(0))
+			.unwrap();
+		opus.decode_bytes(Bytes::from_static(&[TOC]), ms(20)).unwrap();
+
+		// A new OpusHead replaces the track in the middle of the DTX run.
+		opus.initialize(&mut opus_head()).unwrap();
+		let mut consumer = opus.track().unwrap().consume(std::time::Duration::from_secs(10));
+
+		opus.decode_bytes(Bytes::from_static(&[TOC]), ms(420)).unwrap();
+		opus.decode_bytes(Bytes::from_static(&[TOC]), ms(820)).unwrap();
+
+		let first = consumer.read().await.unwrap().unwrap();
+		assert!(first.keyframe, "the first packet on the new track starts a group");
+		assert_eq!(first.timestamp, ms(420).unwrap());
+
+		let second = consumer.read().await.unwrap().unwrap();
+		assert!(!second.keyframe, "the DTX run continues in the same group");
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn dtx_runs_share_a_group() {
+		let (mut opus, mut consumer) = setup();
+
+		opus.decode_bytes(Bytes::from_static(&[TOC, 0xff, 0xfe]), None).unwrap();
+		for _ in 0..2 {
+			tokio::time::advance(std::time::Duration::from_millis(400)).await;
+			opus.decode_bytes(Bytes::from_static(&[TOC]), None).unwrap();
+		}
+		opus.decode_bytes(Bytes::from_static(&[TOC, 0xff, 0xfe]), None).unwrap();
+
+		let mut keyframes = Vec::new();
+		let mut timestamps = Vec::new();
+		for _ in 0..4 {
+			let frame = consumer.read().await.unwrap().unwrap();
+			keyframes.push(frame.keyframe);
+			timestamps.push(frame.timestamp.as_micros());
+		}
+
+		assert_eq!(keyframes, [true, true, false, true]);
+		assert_eq!(timestamps, [0, 20_000, 800_000, 820_000]);
 	}
 }
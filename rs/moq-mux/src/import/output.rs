@@ -0,0 +1,266 @@
+use anyhow::Context;
+use hang::catalog::{AudioConfig, VideoConfig};
+use hang::container::{Frame, OrderedProducer};
+
+use super::{ImportOptions, MediaInfo, SeekIndex};
+
+// A parsed track, before it's added to the catalog.
+#[derive(Debug)]
+pub(crate) enum TrackConfig {
+	#[cfg_attr(not(any(feature = "mp4", feature = "h264", feature = "h265")), allow(dead_code))]
+	Video(VideoConfig),
+	#[cfg_attr(not(any(feature = "mp4", feature = "aac", feature = "opus")), allow(dead_code))]
+	Audio(AudioConfig),
+}
+
+impl TrackConfig {
+	pub fn kind(&self) -> TrackKind {
+		match self {
+			Self::Video(_) => TrackKind::Video,
+			Self::Audio(_) => TrackKind::Audio,
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum TrackKind {
+	Video,
+	Audio,
+}
+
+// The state shared by every importer: the tracks it published, and the options it was configured with.
+//
+// In dry run mode, nothing is published and a description of the media is collected instead.
+// Every track is removed from the catalog when replaced or dropped.
+pub(crate) struct Output {
+	broadcast: moq_lite::BroadcastProducer,
+	catalog: hang::CatalogProducer,
+
+	// The base name of audio tracks, ex. "audio".
+	name: String,
+
+	options: ImportOptions,
+
+	// Collects a description of the media instead of publishing it, if enabled.
+	info: Option<MediaInfo>,
+
+	// The tracks in the catalog, removed when replaced or dropped.
+	tracks: Vec<(TrackKind, moq_lite::Track)>,
+
+	// The track written by [Self::write], for importers that produce a single track.
+	track: Option<OrderedProducer>,
+}
+
+impl Output {
+	#[cfg_attr(not(any(feature = "mp4", feature = "h264", feature = "h265")), allow(dead_code))]
+	pub fn new(broadcast: moq_lite::BroadcastProducer, catalog: hang::CatalogProducer) -> Self {
+		Self::named(broadcast, catalog, "audio")
+	}
+
+	// Name audio tracks using the given base name instead of "audio".
+	pub fn named(
+		broadcast: moq_lite::BroadcastProducer,
+		catalog: hang::CatalogProducer,
+		name: impl Into<String>,
+	) -> Self {
+		Self {
+			broadcast,
+			catalog,
+			name: name.into(),
+			options: ImportOptions::default(),
+			info: None,
+			tracks: Vec::new(),
+			track: None,
+		}
+	}
+
+	pub fn options(&self) -> &ImportOptions {
+		&self.options
+	}
+
+	pub fn set_options(&mut self, options: ImportOptions) {
+		self.info = options.dry_run.then(MediaInfo::default);
+		self.options = options;
+	}
+
+	pub fn set_jitter(&mut self, jitter: Option<std::time::Duration>) {
+		self.options.jitter = jitter;
+	}
+
+	// The fixed jitter to advertise in the catalog, if configured.
+	pub fn jitter(&self) -> anyhow::Result<Option<moq_lite::Time>> {
+		Ok(self.options.jitter.map(moq_lite::Time::try_from).transpose()?)
+	}
+
+	pub fn max_frame_size(&self) -> usize {
+		self.options.max_frame_size
+	}
+
+	pub fn media_info(&self) -> Option<&MediaInfo> {
+		self.info.as_ref()
+	}
+
+	// Replace the previous tracks with one per config in a single catalog update.
+	//
+	// In dry run mode, the configs are recorded instead and no tracks are returned.
+	pub fn replace(&mut self, extension: &str, configs: Vec<TrackConfig>) -> Vec<moq_lite::TrackProducer> {
+		self.track = None;
+
+		if let Some(info) = &mut self.info {
+			info.video.clear();
+			info.audio.clear();
+
+			for config in configs {
+				match config {
+					TrackConfig::Video(config) => info.video.push(config),
+					TrackConfig::Audio(config) => info.audio.push(config),
+				}
+			}
+
+			return Vec::new();
+		}
+
+		let tracks: Vec<_> = {
+			let mut catalog = self.catalog.lock();
+
+			for (kind, track) in self.tracks.drain(..) {
+				tracing::debug!(name = ?track.name, "reinitializing track");
+				remove_track(&mut catalog, kind, &track);
+			}
+
+			configs
+				.into_iter()
+				.map(|config| {
+					let kind = config.kind();
+					let track = match &config {
+						TrackConfig::Video(video) => catalog.video.create_track(extension, video.clone()),
+						TrackConfig::Audio(audio) => {
+							catalog.audio.create_named_track(&self.name, extension, audio.clone())
+						}
+					};
+					tracing::debug!(name = ?track.name, ?config, "starting track");
+
+					(kind, track)
+				})
+				.collect()
+		};
+
+		// The new tracks start their group sequence over.
+		if let Some(index) = &mut self.options.seek_index {
+			index.clear();
+		}
+
+		self.tracks = tracks.clone();
+		tracks
+			.into_iter()
+			.map(|(_, track)| self.broadcast.create_track(track))
+			.collect()
+	}
+}
+
+// Used by the importers that produce a single track, written by [Self::write].
+#[cfg_attr(
+	not(any(feature = "h264", feature = "h265", feature = "aac", feature = "opus")),
+	allow(dead_code)
+)]
+impl Output {
+	pub fn seek_index(&self) -> Option<&SeekIndex> {
+		self.options.seek_index.as_ref()
+	}
+
+	// Whether any track has been created, or recorded in dry run mode.
+	pub fn is_initialized(&self) -> bool {
+		match &self.info {
+			Some(info) => !info.video.is_empty() || !info.audio.is_empty(),
+			None => !self.tracks.is_empty(),
+		}
+	}
+
+	pub fn track(&self) -> Option<&OrderedProducer> {
+		self.track.as_ref()
+	}
+
+	pub fn track_name(&self) -> Option<&str> {
+		self.track.as_ref().map(|track| track.info.name.as_str())
+	}
+
+	// Replace the previous track of an importer that produces a single track, see [Self::write].
+	pub fn replace_track(&mut self, extension: &str, config: TrackConfig) {
+		self.track = self.replace(extension, vec![config]).pop().map(Into::into);
+	}
+
+	// Write a frame to the track, recording each group in the seek index, or count it in dry run mode.
+	//
+	// Returns the track so the caller can flush it, or [None] in dry run mode.
+	pub fn write(&mut self, frame: Frame) -> anyhow::Result<Option<&mut OrderedProducer>> {
+		if let Some(info) = &mut self.info {
+			anyhow::ensure!(!info.video.is_empty() || !info.audio.is_empty(), "not initialized");
+			info.frames += 1;
+
+			return Ok(None);
+		}
+
+		let track = self.track.as_mut().context("not initialized")?;
+
+		let keyframe = frame.keyframe.then_some(frame.timestamp);
+		track.write(frame)?;
+
+		if let Some(timestamp) = keyframe
+			&& let Some(index) = &mut self.options.seek_index
+		{
+			index.push(timestamp, track.group_sequence().context("missing group")?);
+		}
+
+		Ok(Some(track))
+	}
+}
+
+// Used by the fMP4 importer, which produces a track per trak.
+#[cfg_attr(not(feature = "mp4"), allow(dead_code))]
+impl Output {
+	pub fn is_dry_run(&self) -> bool {
+		self.info.is_some()
+	}
+
+	pub fn catalog(&mut self) -> &mut hang::CatalogProducer {
+		&mut self.catalog
+	}
+
+	// Count a frame in dry run mode, returning false if it should be written instead.
+	pub fn count_frame(&mut self) -> bool {
+		match &mut self.info {
+			Some(info) => {
+				info.frames += 1;
+				true
+			}
+			None => false,
+		}
+	}
+}
+
+impl Drop for Output {
+	fn drop(&mut self) {
+		if self.tracks.is_empty() {
+			return;
+		}
+
+		let tracks = std::mem::take(&mut self.tracks);
+		for (_, track) in &tracks {
+			tracing::debug!(name = ?track.name, "ending track");
+		}
+
+		// Never block during teardown; the removal is queued if the lock is held elsewhere.
+		self.catalog.queue(move |catalog| {
+			for (kind, track) in tracks {
+				remove_track(catalog, kind, &track);
+			}
+		});
+	}
+}
+
+fn remove_track(catalog: &mut hang::Catalog, kind: TrackKind, track: &moq_lite::Track) {
+	match kind {
+		TrackKind::Video => catalog.video.remove_track(track).is_some(),
+		TrackKind::Audio => catalog.audio.remove_track(track).is_some(),
+	};
+}
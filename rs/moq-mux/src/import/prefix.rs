@@ -0,0 +1,100 @@
+use bytes::{Buf, Bytes};
+
+use super::ensure_frame_size;
+
+/// The length prefix preceding each packet when stream decoding raw audio frames.
+///
+/// Raw AAC and Opus frames have no inherent boundaries, but many transports prefix each packet with its size.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LengthPrefixed {
+	/// A 1 byte length.
+	U8,
+	/// A 2 byte length, big endian.
+	U16Be,
+	/// A 2 byte length, little endian.
+	U16Le,
+	/// A 4 byte length, big endian.
+	U32Be,
+	/// A 4 byte length, little endian.
+	U32Le,
+}
+
+impl LengthPrefixed {
+	/// The size of the length prefix in bytes.
+	pub fn size(&self) -> usize {
+		match self {
+			Self::U8 => 1,
+			Self::U16Be | Self::U16Le => 2,
+			Self::U32Be | Self::U32Le => 4,
+		}
+	}
+
+	/// Split the next packet off the front of the buffer, returning [None] if it's not complete yet.
+	///
+	/// The length prefix is consumed along with the packet.
+	/// Returns an error if the packet is larger than `max_size`, rather than waiting for it.
+	pub(crate) fn next<T: Buf + AsRef<[u8]>>(&self, buf: &mut T, max_size: usize) -> anyhow::Result<Option<Bytes>> {
+		let data = buf.as_ref();
+		let Some(header) = data.get(..self.size()) else {
+			return Ok(None);
+		};
+
+		let size = match self {
+			Self::U8 => header[0] as usize,
+			Self::U16Be => u16::from_be_bytes(header.try_into().unwrap()) as usize,
+			Self::U16Le => u16::from_le_bytes(header.try_into().unwrap()) as usize,
+			Self::U32Be => u32::from_be_bytes(header.try_into().unwrap()) as usize,
+			Self::U32Le => u32::from_le_bytes(header.try_into().unwrap()) as usize,
+		};
+
+		ensure_frame_size(size, max_size)?;
+
+		if data.len() < self.size() + size {
+			return Ok(None);
+		}
+
+		buf.advance(self.size());
+		Ok(Some(buf.copy_to_bytes(size)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn next() {
+		let cases: [(LengthPrefixed, &[u8]); 5] = [
+			(LengthPrefixed::U8, &[0x03]),
+			(LengthPrefixed::U16Be, &[0x00, 0x03]),
+			(LengthPrefixed::U16Le, &[0x03, 0x00]),
+			(LengthPrefixed::U32Be, &[0x00, 0x00, 0x00, 0x03]),
+			(LengthPrefixed::U32Le, &[0x03, 0x00, 0x00, 0x00]),
+		];
+
+		for (prefix, header) in cases {
+			let mut data = header.to_vec();
+			data.extend([0x01, 0x02, 0x03]);
+
+			// Incomplete until the entire packet is available.
+			for size in 0..data.len() {
+				let mut buf = &data[..size];
+				assert_eq!(prefix.next(&mut buf, 3).unwrap(), None, "{prefix:?}");
+				assert_eq!(buf.len(), size);
+			}
+
+			let mut buf = Bytes::from(data);
+			assert_eq!(prefix.next(&mut buf, 3).unwrap().unwrap().as_ref(), &[0x01, 0x02, 0x03]);
+			assert!(buf.is_empty());
+		}
+	}
+
+	#[test]
+	fn too_large() {
+		// The declared size is rejected before the packet is buffered.
+		let mut buf: &[u8] = &[0xff, 0xff, 0xff, 0xff];
+		let err = LengthPrefixed::U32Be.next(&mut buf, 1024).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(hang::Error::InvalidFrame)));
+		assert_eq!(buf.len(), 4);
+	}
+}
@@ -0,0 +1,141 @@
+use std::collections::VecDeque;
+
+use hang::container::Timestamp;
+
+/// The starting keyframe of a group, recorded in a [SeekIndex].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeekPoint {
+	/// The timestamp of the keyframe that starts the group.
+	pub timestamp: Timestamp,
+
+	/// The sequence number of the group.
+	pub group: u64,
+}
+
+/// An index of the keyframe that starts each group, so a player can seek without scanning the track.
+///
+/// This is opt-in because an unbounded index grows with the length of the track.
+/// Use [Self::with_capacity] for live streams to only keep the most recent groups.
+#[derive(Clone, Debug, Default)]
+pub struct SeekIndex {
+	points: VecDeque<SeekPoint>,
+	#[cfg_attr(
+		not(any(
+			feature = "h264",
+			feature = "mp4",
+			feature = "h265",
+			feature = "aac",
+			feature = "opus"
+		)),
+		allow(dead_code)
+	)]
+	capacity: Option<usize>,
+}
+
+impl SeekIndex {
+	/// Create an unbounded index, recording every group.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Create an index that only keeps the most recent `capacity` groups.
+	pub fn with_capacity(capacity: usize) -> Self {
+		Self {
+			points: VecDeque::with_capacity(capacity),
+			capacity: Some(capacity),
+		}
+	}
+
+	/// The recorded groups, ordered by timestamp.
+	pub fn points(&self) -> impl Iterator<Item = &SeekPoint> {
+		self.points.iter()
+	}
+
+	pub fn len(&self) -> usize {
+		self.points.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.points.is_empty()
+	}
+
+	/// Find the group to start from in order to play the given timestamp.
+	///
+	/// This is the last group starting at or before the timestamp, or [None] if it's before the first recorded group.
+	pub fn seek(&self, timestamp: Timestamp) -> Option<SeekPoint> {
+		let index = self.points.partition_point(|point| point.timestamp <= timestamp);
+		index.checked_sub(1).map(|index| self.points[index])
+	}
+
+	// Only the importers record groups.
+	#[cfg_attr(
+		not(any(
+			feature = "h264",
+			feature = "mp4",
+			feature = "h265",
+			feature = "aac",
+			feature = "opus"
+		)),
+		allow(dead_code)
+	)]
+	pub(crate) fn push(&mut self, timestamp: Timestamp, group: u64) {
+		if let Some(capacity) = self.capacity {
+			if capacity == 0 {
+				return;
+			}
+
+			while self.points.len() >= capacity {
+				self.points.pop_front();
+			}
+		}
+
+		self.points.push_back(SeekPoint { timestamp, group });
+	}
+
+	// Remove every entry, used when the track is replaced and the group sequences start over.
+	#[cfg_attr(
+		not(any(
+			feature = "h264",
+			feature = "mp4",
+			feature = "h265",
+			feature = "aac",
+			feature = "opus"
+		)),
+		allow(dead_code)
+	)]
+	pub(crate) fn clear(&mut self) {
+		self.points.clear();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn ms(ms: u64) -> Timestamp {
+		Timestamp::from_micros(ms * 1_000).unwrap()
+	}
+
+	#[test]
+	fn seek() {
+		let mut index = SeekIndex::new();
+		index.push(ms(1000), 0);
+		index.push(ms(3000), 1);
+
+		assert_eq!(index.seek(ms(0)), None);
+		assert_eq!(index.seek(ms(1000)).unwrap().group, 0);
+		assert_eq!(index.seek(ms(2999)).unwrap().group, 0);
+		assert_eq!(index.seek(ms(5000)).unwrap().group, 1);
+	}
+
+	#[test]
+	fn bounded() {
+		let mut index = SeekIndex::with_capacity(2);
+		for group in 0..5 {
+			index.push(ms(group * 1000), group);
+		}
+
+		let groups: Vec<_> = index.points().map(|point| point.group).collect();
+		assert_eq!(groups, [3, 4]);
+	}
+}
@@ -80,7 +80,7 @@ impl Default for ClientWebSocket {
 }
 
 /// Configuration for the MoQ client.
-#[derive(Clone, Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, clap::Parser, serde::Serialize, serde::Deserialize)]
 #[serde(deny_unknown_fields, default)]
 #[non_exhaustive]
 pub struct ClientConfig {
@@ -100,6 +100,19 @@ pub struct ClientConfig {
 	#[command(flatten)]
 	#[serde(default)]
 	pub websocket: ClientWebSocket,
+
+	/// An authentication token (ex. a JWT from moq-token) presented to the server.
+	///
+	/// The token is sent as the `?jwt=` query parameter, which is what moq-relay expects.
+	/// This works for WebTransport (`https://`), WebSocket, and iroh HTTP/3 (`h3+iroh://`) URLs.
+	/// Raw QUIC (`moql://`, `moqt://`, `iroh://`) does not transmit the URL, so the token is ignored with a warning.
+	///
+	/// moq-native never logs the token itself.
+	/// However, web-transport and tungstenite log the connect URL at debug level, which includes the token.
+	/// [`Log`](crate::Log) caps those targets at info, so install a similar filter when using your own subscriber.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[arg(id = "client-token", long = "client-token", env = "MOQ_CLIENT_TOKEN")]
+	pub token: Option<String>,
 }
 
 impl ClientConfig {
@@ -114,10 +127,23 @@ impl Default for ClientConfig {
 			bind: "[::]:0".parse().unwrap(),
 			tls: ClientTls::default(),
 			websocket: ClientWebSocket::default(),
+			token: None,
 		}
 	}
 }
 
+// Implemented manually so the token is never printed.
+impl std::fmt::Debug for ClientConfig {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("ClientConfig")
+			.field("bind", &self.bind)
+			.field("tls", &self.tls)
+			.field("websocket", &self.websocket)
+			.field("token", &self.token.as_ref().map(|_| "<redacted>"))
+			.finish()
+	}
+}
+
 /// Client for establishing MoQ connections over QUIC, WebTransport, or WebSocket.
 ///
 /// Create via [`ClientConfig::init`] or [`Client::new`].
@@ -131,6 +157,7 @@ pub struct Client {
 	pub websocket: ClientWebSocket,
 	#[cfg(feature = "iroh")]
 	pub iroh: Option<iroh::Endpoint>,
+	token: Option<String>,
 }
 
 impl Client {
@@ -207,6 +234,7 @@ impl Client {
 			websocket: config.websocket,
 			#[cfg(feature = "iroh")]
 			iroh: None,
+			token: config.token,
 		})
 	}
 
@@ -226,6 +254,12 @@ impl Client {
 		self
 	}
 
+	/// Present the given authentication token when connecting, see [`ClientConfig::token`].
+	pub fn with_token(mut self, token: impl Into<Option<String>>) -> Self {
+		self.token = token.into();
+		self
+	}
+
 	// TODO: Uncomment when observability feature is merged
 	// pub fn with_stats(mut self, stats: impl Into<Option<Arc<dyn moq_lite::Stats>>>) -> Self {
 	// 	self.moq = self.moq.with_stats(stats);
@@ -330,8 +364,13 @@ impl Client {
 		tracing::Span::current().record("id", connection.stable_id());
 
 		let session = match alpn {
-			web_transport_quinn::ALPN => web_transport_quinn::Session::connect(connection, url).await?,
-			moq_lite::lite::ALPN | moq_lite::ietf::ALPN => web_transport_quinn::Session::raw(connection, url),
+			web_transport_quinn::ALPN => {
+				web_transport_quinn::Session::connect(connection, authorize(url, self.token.as_deref())).await?
+			}
+			moq_lite::lite::ALPN | moq_lite::ietf::ALPN => {
+				self.warn_unsupported_token();
+				web_transport_quinn::Session::raw(connection, url)
+			}
 			_ => unreachable!("ALPN was checked above"),
 		};
 
@@ -386,7 +425,7 @@ impl Client {
 
 		// Connect using tokio-tungstenite
 		let (ws_stream, _response) = tokio_tungstenite::connect_async_tls_with_config(
-			url.as_str(),
+			authorize(url.clone(), self.token.as_deref()).as_str(),
 			Some(tungstenite::protocol::WebSocketConfig {
 				max_message_size: Some(64 << 20), // 64 MB
 				max_frame_size: Some(16 << 20),   // 16 MB
@@ -426,12 +465,21 @@ impl Client {
 				// We need to change the scheme to `https` because currently web_transport_iroh only
 				// accepts that scheme.
 				let url = url_set_scheme(url, "https")?;
-				web_transport_iroh::Session::connect_h3(conn, url).await?
+				web_transport_iroh::Session::connect_h3(conn, authorize(url, self.token.as_deref())).await?
+			}
+			_ => {
+				self.warn_unsupported_token();
+				web_transport_iroh::Session::raw(conn)
 			}
-			_ => web_transport_iroh::Session::raw(conn),
 		};
 		Ok(session)
 	}
+
+	fn warn_unsupported_token(&self) {
+		if self.token.is_some() {
+			tracing::warn!("raw QUIC does not support authentication tokens; ignoring");
+		}
+	}
 }
 
 #[derive(Debug)]
@@ -542,6 +590,22 @@ fn url_set_scheme(url: Url, scheme: &str) -> anyhow::Result<Url> {
 	Ok(url)
 }
 
+/// Set the token as the `?jwt=` query parameter, replacing any token already in the URL.
+///
+/// NOTE: The returned URL contains a secret, so don't log it.
+fn authorize(mut url: Url, token: Option<&str>) -> Url {
+	let Some(token) = token else {
+		return url;
+	};
+
+	let pairs: Vec<(String, String)> = url.query_pairs().into_owned().filter(|(key, _)| key != "jwt").collect();
+	url.query_pairs_mut()
+		.clear()
+		.extend_pairs(pairs)
+		.append_pair("jwt", token);
+	url
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -578,4 +642,35 @@ mod tests {
 		let config = ClientConfig::parse_from(["test"]);
 		assert_eq!(config.tls.disable_verify, None);
 	}
+
+	#[test]
+	fn test_cli_token() {
+		let config = ClientConfig::parse_from(["test", "--client-token", "secret"]);
+		assert_eq!(config.token.as_deref(), Some("secret"));
+	}
+
+	#[test]
+	fn test_authorize() {
+		let url: Url = "https://relay.example.com/demo?foo=bar".parse().unwrap();
+		assert_eq!(authorize(url.clone(), None), url);
+		assert_eq!(
+			authorize(url, Some("secret")).as_str(),
+			"https://relay.example.com/demo?foo=bar&jwt=secret"
+		);
+
+		// An existing token is replaced rather than duplicated.
+		let url: Url = "https://relay.example.com/demo?jwt=old&foo=bar".parse().unwrap();
+		assert_eq!(
+			authorize(url, Some("new")).as_str(),
+			"https://relay.example.com/demo?foo=bar&jwt=new"
+		);
+	}
+
+	#[test]
+	fn test_debug_redacts_token() {
+		let config = ClientConfig::parse_from(["test", "--client-token", "secret"]);
+		let debug = format!("{config:?}");
+		assert!(!debug.contains("secret"));
+		assert!(debug.contains("<redacted>"));
+	}
 }
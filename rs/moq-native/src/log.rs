@@ -35,15 +35,9 @@ impl Log {
 	}
 
 	pub fn init(&self) {
-		let filter = EnvFilter::builder()
-			.with_default_directive(self.level().into()) // Default to our -q/-v args
-			.from_env_lossy() // Allow overriding with RUST_LOG
-			.add_directive("h2=warn".parse().unwrap())
-			.add_directive("quinn=info".parse().unwrap())
-			.add_directive("tracing::span=off".parse().unwrap())
-			.add_directive("tracing::span::active=off".parse().unwrap())
-			.add_directive("tokio=info".parse().unwrap())
-			.add_directive("runtime=info".parse().unwrap());
+		// Allow overriding with RUST_LOG
+		let env = std::env::var(EnvFilter::DEFAULT_ENV).unwrap_or_default();
+		let filter = self.filter(&env);
 
 		let fmt_layer = tracing_subscriber::fmt::layer()
 			.with_writer(std::io::stderr)
@@ -68,6 +62,24 @@ impl Log {
 		std::thread::spawn(Self::deadlock_detector);
 	}
 
+	fn filter(&self, env: &str) -> EnvFilter {
+		EnvFilter::builder()
+			.with_default_directive(self.level().into()) // Default to our -q/-v args
+			.parse_lossy(env)
+			.add_directive("h2=warn".parse().unwrap())
+			.add_directive("quinn=info".parse().unwrap())
+			.add_directive("tracing::span=off".parse().unwrap())
+			.add_directive("tracing::span::active=off".parse().unwrap())
+			.add_directive("tokio=info".parse().unwrap())
+			.add_directive("runtime=info".parse().unwrap())
+			// These log the CONNECT request or WebSocket handshake at debug level, which includes the `?jwt=` token.
+			// They're capped even if RUST_LOG asks for more, so a token is never logged.
+			.add_directive("web_transport_quinn=info".parse().unwrap())
+			.add_directive("web_transport_iroh=info".parse().unwrap())
+			.add_directive("tungstenite=info".parse().unwrap())
+			.add_directive("tokio_tungstenite=info".parse().unwrap())
+	}
+
 	#[cfg(debug_assertions)]
 	fn deadlock_detector() {
 		loop {
@@ -92,3 +104,25 @@ impl Log {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn filter_hides_connect_url() {
+		let log = Log::default();
+		let filter = log.filter("trace,web_transport_quinn=trace,tungstenite=trace");
+		let subscriber = tracing_subscriber::registry().with(filter);
+
+		tracing::subscriber::with_default(subscriber, || {
+			assert!(tracing::enabled!(target: "moq_native::client", Level::DEBUG));
+
+			// These targets log the URL, including the token.
+			assert!(!tracing::enabled!(target: "web_transport_quinn::connect", Level::DEBUG));
+			assert!(!tracing::enabled!(target: "web_transport_iroh::connect", Level::DEBUG));
+			assert!(!tracing::enabled!(target: "tungstenite::handshake::client", Level::TRACE));
+			assert!(!tracing::enabled!(target: "tokio_tungstenite", Level::DEBUG));
+		});
+	}
+}